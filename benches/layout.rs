@@ -0,0 +1,163 @@
+//! Compares the flat weight-matrix `Network` against the nested
+//! `Vec<Layer>` → `Vec<Neuron>` → `Vec<f32>` layout it replaced.
+//!
+//! Run with `cargo bench`.
+
+#[macro_use]
+extern crate log;
+extern crate rand;
+extern crate rustc_serialize;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/neural/mod.rs"]
+mod neural;
+
+use neural::{Crossover, MutationConfig, Network, Scratch};
+use rand::Rng;
+use std::time::Instant;
+
+/// The nested layout, kept here only for comparison
+struct NestedNetwork {
+    layers: Vec<NestedLayer>,
+}
+
+struct NestedLayer {
+    neurons: Vec<NestedNeuron>,
+}
+
+struct NestedNeuron {
+    bias: f32,
+    weights: Vec<f32>,
+}
+
+impl NestedNetwork {
+    fn from_network(net: &Network) -> NestedNetwork {
+        NestedNetwork {
+            layers: net.layers.iter()
+                .map(|layer| {
+                    NestedLayer {
+                        neurons: (0..layer.num_neurons())
+                            .map(|i| {
+                                NestedNeuron {
+                                    bias: layer.biases[i],
+                                    weights: layer.neuron_weights(i).to_vec(),
+                                }
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn run(&self, inputs: &[f32]) -> Vec<Vec<f32>> {
+        let mut outputs = Vec::new();
+        let mut prev_layer_out = Vec::from(inputs);
+
+        for layer in &self.layers {
+            let mut layer_out = Vec::new();
+
+            for neuron in &layer.neurons {
+                let mut sum = neuron.bias;
+
+                for (weight, input) in neuron.weights.iter().zip(&prev_layer_out) {
+                    sum += input * weight;
+                }
+
+                layer_out.push(1f32 / (1f32 + (-sum).exp()));
+            }
+            outputs.push(prev_layer_out);
+            prev_layer_out = layer_out;
+        }
+
+        outputs.push(prev_layer_out);
+
+        outputs
+    }
+
+    fn reproduce(&self, partner: &NestedNetwork) -> NestedNetwork {
+        let mut rng = rand::thread_rng();
+
+        let layers = self.layers.iter()
+            .zip(&partner.layers)
+            .map(|(self_layer, partner_layer)| {
+                let neurons = self_layer.neurons.iter()
+                    .zip(&partner_layer.neurons)
+                    .map(|(self_neuron, partner_neuron)| {
+                        let weights = self_neuron.weights.iter()
+                            .zip(&partner_neuron.weights)
+                            .map(|(&a, &b)| {
+                                if rng.gen_weighted_bool(50) {
+                                    rng.gen::<f32>() * 2f32 - 1f32
+                                }
+                                else if rng.gen() { a } else { b }
+                            })
+                            .collect();
+
+                        NestedNeuron {
+                            bias: if rng.gen() { self_neuron.bias } else { partner_neuron.bias },
+                            weights: weights,
+                        }
+                    })
+                    .collect();
+
+                NestedLayer {
+                    neurons: neurons,
+                }
+            })
+            .collect();
+
+        NestedNetwork {
+            layers: layers,
+        }
+    }
+}
+
+/// Times `f` over the given number of iterations and prints the mean
+fn bench<F: FnMut() -> f32>(name: &str, iterations: u32, mut f: F) {
+    let mut checksum = 0f32;
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        checksum += f();
+    }
+
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+
+    // Printing the checksum keeps the work from being optimised away
+    println!("{:<32} {:>12} ns/iter   (checksum {})", name, nanos / iterations as u64, checksum);
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+
+    for dimensions in &[&[9, 9, 9][..], &[9, 64, 64, 9][..], &[256, 256, 256][..]] {
+        let iterations = 1_000_000 / dimensions.iter().fold(1, |sum, size| sum + size * size) as u32 + 10;
+
+        let flat = Network::with_dimensions(dimensions);
+        let partner = Network::with_dimensions(dimensions);
+        let nested = NestedNetwork::from_network(&flat);
+        let nested_partner = NestedNetwork::from_network(&partner);
+
+        let inputs = (0..dimensions[0])
+            .map(|_| rng.gen::<f32>() * 2f32 - 1f32)
+            .collect::<Vec<_>>();
+        let mut scratch = Scratch::new();
+        let mutation = MutationConfig::default();
+
+        println!("{:?}", dimensions);
+
+        bench("nested run", iterations, || nested.run(&inputs)[dimensions.len() - 1][0]);
+        bench("flat run", iterations, || flat.run(&inputs)[dimensions.len() - 1][0]);
+        bench("flat evaluate", iterations, || flat.evaluate(&inputs, &mut scratch)[0]);
+        bench("nested reproduce", iterations / 10 + 1, || {
+            nested.reproduce(&nested_partner).layers[0].neurons[0].weights[0]
+        });
+        bench("flat reproduce", iterations / 10 + 1, || {
+            flat.reproduce(&partner, Crossover::Uniform, &mutation, &mut rng).layers[0].weights[0]
+        });
+
+        println!("");
+    }
+}
@@ -0,0 +1,220 @@
+//! Measures saved players against fixed reference opponents.
+//!
+//! Each player plays the same number of games as X and as O. Rates are
+//! given with 95% Wilson score intervals, which stay within 0 to 1 and
+//! behave sensibly when a rate is at either end.
+
+use game::{play_game, AiPlayer, GameResult, Player};
+use rand::Rng;
+use std::fmt;
+
+/// The normal quantile for a 95% confidence interval
+const Z: f64 = 1.96;
+
+/// A rate with the bounds of its confidence interval
+#[derive(RustcEncodable, Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+    pub rate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Interval {
+
+    /// Estimates the rate of `successes` out of `trials` with a Wilson score
+    /// interval
+    pub fn wilson(successes: u32, trials: u32) -> Interval {
+        if trials == 0 {
+            return Interval { rate: 0f64, lower: 0f64, upper: 1f64 };
+        }
+
+        let n = trials as f64;
+        let p = successes as f64 / n;
+        let denominator = 1f64 + Z * Z / n;
+        let centre = (p + Z * Z / (2f64 * n)) / denominator;
+        let spread = Z * (p * (1f64 - p) / n + Z * Z / (4f64 * n * n)).sqrt() / denominator;
+
+        Interval {
+            rate: p,
+            lower: (centre - spread).max(0f64),
+            upper: (centre + spread).min(1f64),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:5.1}% ({:.1}-{:.1}%)", self.rate * 100f64, self.lower * 100f64, self.upper * 100f64)
+    }
+}
+
+/// The results of a set of games
+#[derive(RustcEncodable, Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub games: u32,
+    pub wins: u32,
+    pub ties: u32,
+    pub losses: u32,
+    /// Moves the player tried on occupied spaces before finding a legal one
+    pub illegal_moves: u32,
+    pub win_rate: Interval,
+    pub tie_rate: Interval,
+    pub loss_rate: Interval,
+}
+
+impl Summary {
+    fn new(wins: u32, ties: u32, losses: u32, illegal_moves: u32) -> Summary {
+        let games = wins + ties + losses;
+
+        Summary {
+            games: games,
+            wins: wins,
+            ties: ties,
+            losses: losses,
+            illegal_moves: illegal_moves,
+            win_rate: Interval::wilson(wins, games),
+            tie_rate: Interval::wilson(ties, games),
+            loss_rate: Interval::wilson(losses, games),
+        }
+    }
+
+    fn combine(&self, other: &Summary) -> Summary {
+        Summary::new(self.wins + other.wins,
+                     self.ties + other.ties,
+                     self.losses + other.losses,
+                     self.illegal_moves + other.illegal_moves)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let per_game = if self.games == 0 { 0f64 } else { self.illegal_moves as f64 / self.games as f64 };
+
+        write!(f, "{:>5} games  wins {}  ties {}  losses {}  illegal moves {} ({:.2} a game)",
+            self.games, self.win_rate, self.tie_rate, self.loss_rate, self.illegal_moves, per_game)
+    }
+}
+
+/// How one player fared against an opponent
+#[derive(RustcEncodable, Clone, Debug, PartialEq)]
+pub struct Report {
+    /// The player's index in the saved population
+    pub player: usize,
+    pub opponent: String,
+    pub as_x: Summary,
+    pub as_o: Summary,
+    pub overall: Summary,
+    /// The fraction of every reachable position the player plays
+    /// perfectly, if it was measured
+    pub coverage: Option<f64>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Player {} against {}", self.player, self.opponent)?;
+        writeln!(f, "  as X:    {}", self.as_x)?;
+        writeln!(f, "  as O:    {}", self.as_o)?;
+        write!(f, "  overall: {}", self.overall)?;
+
+        if let Some(coverage) = self.coverage {
+            write!(f, "\n  plays {:.1}% of positions perfectly", coverage * 100f64)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays `games` games as X and as O against the opponent
+pub fn run<P: Player, R: Rng>(index: usize,
+                              player: &AiPlayer,
+                              opponent_name: &str,
+                              opponent: &mut P,
+                              games: u32,
+                              rng: &mut R)
+                              -> Report {
+    let as_x = play_side(player, opponent, games, true, rng);
+    let as_o = play_side(player, opponent, games, false, rng);
+
+    Report {
+        player: index,
+        opponent: opponent_name.to_string(),
+        overall: as_x.combine(&as_o),
+        as_x: as_x,
+        as_o: as_o,
+        coverage: None,
+    }
+}
+
+fn play_side<P: Player, R: Rng>(player: &AiPlayer,
+                                opponent: &mut P,
+                                games: u32,
+                                first: bool,
+                                rng: &mut R)
+                                -> Summary {
+    let (mut wins, mut ties, mut losses, mut illegal_moves) = (0, 0, 0, 0);
+
+    for _ in 0..games {
+        // A fresh copy for each game keeps its counters from overflowing
+        let mut copy = AiPlayer::with_network(player.neural_net.clone());
+
+        let result = if first {
+            play_game(&mut copy, opponent, rng)
+        } else {
+            match play_game(opponent, &mut copy, rng) {
+                GameResult::Win => GameResult::Loss,
+                GameResult::Loss => GameResult::Win,
+                GameResult::Tie => GameResult::Tie,
+            }
+        };
+
+        match result {
+            GameResult::Win => wins += 1,
+            GameResult::Tie => ties += 1,
+            GameResult::Loss => losses += 1,
+        }
+        illegal_moves += copy.mistakes;
+    }
+
+    Summary::new(wins, ties, losses, illegal_moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{AiConfig, MinimaxPlayer};
+    use genetics::Individual;
+    use rand::{SeedableRng, StdRng};
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 0.0001, "expected {}, found {}", expected, actual);
+    }
+
+    #[test]
+    fn test_wilson() {
+        let half = Interval::wilson(5, 10);
+        assert_close(0.5, half.rate);
+        assert_close(0.2366, half.lower);
+        assert_close(0.7634, half.upper);
+
+        let none = Interval::wilson(0, 10);
+        assert_eq!(0f64, none.lower);
+        assert_close(0.2775, none.upper);
+
+        assert_eq!(Interval { rate: 0f64, lower: 0f64, upper: 1f64 }, Interval::wilson(0, 0));
+    }
+
+    #[test]
+    fn test_run_against_minimax() {
+        let mut rng = StdRng::from_seed(&[8]);
+        let player = AiPlayer::new(&AiConfig::default(), &mut rng);
+        let mut minimax = MinimaxPlayer::new();
+
+        let report = run(3, &player, "minimax", &mut minimax, 10, &mut rng);
+
+        assert_eq!(3, report.player);
+        assert_eq!(10, report.as_x.games);
+        assert_eq!(10, report.as_o.games);
+        assert_eq!(20, report.overall.games);
+        assert_eq!(0, report.overall.wins);
+        assert_eq!(report.as_x.illegal_moves + report.as_o.illegal_moves, report.overall.illegal_moves);
+    }
+}
@@ -1,16 +1,42 @@
 use std::fmt;
-use super::{board, Player, GameResult, play_game};
-use genetics::Individual;
-use neural::Network;
+use super::{board, Coverage, Player, GameResult, play_game};
+use genetics::{Evaluate, Individual, Rated, RatingSystem, Record};
+use neural::{Activation, Crossover, Example, MutationConfig, Network, Scratch};
+use rand::Rng;
+use std::cell::RefCell;
 
-#[derive(RustcEncodable, RustcDecodable)]
+thread_local!(static SCRATCH: RefCell<Scratch> = RefCell::new(Scratch::new()));
+
+#[derive(RustcEncodable, RustcDecodable, Clone)]
 pub struct AiPlayer {
     pub neural_net: Network,
     mark: board::Mark,
-    pub wins: u16,
-    pub loses: u16,
-    pub ties: u16,
-    pub mistakes: u16,
+    pub wins: u32,
+    pub loses: u32,
+    pub ties: u32,
+    pub mistakes: u32,
+    /// The rating used as fitness instead of the counters, if any
+    pub rated: Option<Rated>,
+}
+
+/// Settings shared by the `AiPlayer`s of an evolution
+#[derive(Clone, Debug, Default)]
+pub struct AiConfig {
+    pub crossover: Crossover,
+    pub mutation: MutationConfig,
+    /// Rates players with this system rather than their counters
+    pub rating: Option<RatingSystem>,
+}
+
+/// A fixed environment an `AiPlayer` can be evaluated in instead of playing
+/// the rest of its generation
+pub enum AiEnvironment {
+    /// Boards labelled with the best moves. Picking any of the best moves
+    /// counts as a win, another empty square as a loss and an occupied square
+    /// as a loss and a mistake.
+    Examples(Vec<Example>),
+    /// Every reachable position, scored the same way as examples
+    Coverage(Coverage),
 }
 
 impl AiPlayer {
@@ -18,10 +44,46 @@ impl AiPlayer {
         AiPlayer {
             neural_net: net,
             mark: board::Mark::None,
-            wins: 0u16,
-            loses: 0u16,
-            ties: 0u16,
-            mistakes: 0u16,
+            wins: 0u32,
+            loses: 0u32,
+            ties: 0u32,
+            mistakes: 0u32,
+            rated: None,
+        }
+    }
+
+    /// Plays a copy of this player against the opponent, alternating who
+    /// goes first, and returns the fraction of points this player won with a
+    /// tie worth half a win
+    pub fn score_against<P: Player, R: Rng>(&self, opponent: &mut P, games: u32, rng: &mut R) -> f64 {
+        let mut player = AiPlayer::with_network(self.neural_net.clone());
+
+        for game in 0..games {
+            if game % 2 == 0 {
+                play_game(&mut player, opponent, rng);
+            } else {
+                play_game(opponent, &mut player, rng);
+            }
+        }
+
+        if games == 0 {
+            0f64
+        } else {
+            (player.wins as f64 + player.ties as f64 / 2f64) / games as f64
+        }
+    }
+
+    /// Scores one move chosen in an environment: an optimal move counts as a
+    /// win, any other as a loss and an occupied space also as a mistake
+    fn score_choice(&mut self, optimal: bool, occupied: bool) {
+        if optimal {
+            self.wins += 1;
+        } else {
+            self.loses += 1;
+
+            if occupied {
+                self.mistakes += 1;
+            }
         }
     }
 }
@@ -32,39 +94,19 @@ impl Player for AiPlayer {
         self.mark = mark;
     }   
 
-    fn play(&mut self, board: &mut board::View) {
-        let mut inputs = [0f32; 9];
-        
+    fn play<R: Rng>(&mut self, board: &mut board::View, rng: &mut R) {
         // Setup inputs based on the board state
-        //for (i, mark) in board.iter().enumerate() {
-        for i in 0..9 {
-            let mark = board[i];
-            
-            if mark == self.mark {
-                inputs[i] = -1f32;
-            } else if mark != board::Mark::None {
-                inputs[i] = 1f32;
-            }
-        }
+        let inputs = board::inputs(&board.cells(), self.mark);
         
-        // Run it through the neural network
-        let outputs = self.neural_net.run(&inputs);
+        // Run it through the neural network and decide the order to try moves
+        let is_softmax = is_softmax(&self.neural_net);
         
-        println!("{:?}", outputs);
-
-        // Make a move
-        let mut sorted_outputs = outputs.iter()
-            .last().unwrap()
-            .iter()
-            .enumerate()
-            .collect::<Vec<_>>();
-        
-        sorted_outputs.sort_by(|&(_, a), &(_, b)| {
-            b.abs().partial_cmp(&a.abs()).unwrap()
+        let moves = SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            move_order(self.neural_net.evaluate(&inputs, &mut scratch), is_softmax, rng)
         });
-        println!("{:?}", sorted_outputs);
         
-        for (i, _) in sorted_outputs {
+        for i in moves {
             if board[i] == board::Mark::None {
                 board[i] = self.mark;
                 return;
@@ -83,6 +125,51 @@ impl Player for AiPlayer {
     }
 }
 
+/// Returns whether the network's outputs are move probabilities
+pub fn is_softmax(network: &Network) -> bool {
+    network.layers.last().map_or(false, |layer| layer.activation == Activation::Softmax)
+}
+
+/// Returns the order in which `AiPlayer` tries moves given its network's
+/// outputs. Probabilities are sampled, while any other outputs are ranked
+/// highest first, so a large negative output is the last resort.
+pub fn move_order<R: Rng>(outputs: &[f32], is_softmax: bool, rng: &mut R) -> Vec<usize> {
+    if is_softmax {
+        return sample_moves(rng, outputs);
+    }
+
+    let mut moves = (0..outputs.len()).collect::<Vec<_>>();
+    moves.sort_by(|&a, &b| outputs[b].partial_cmp(&outputs[a]).unwrap());
+    moves
+}
+
+/// Orders the moves by repeatedly sampling from the remaining probabilities
+fn sample_moves<R: Rng>(rng: &mut R, probabilities: &[f32]) -> Vec<usize> {
+    let mut remaining = probabilities.iter()
+        .cloned()
+        .enumerate()
+        .collect::<Vec<_>>();
+    let mut moves = Vec::with_capacity(remaining.len());
+    
+    while !remaining.is_empty() {
+        let total = remaining.iter().fold(0f32, |sum, &(_, p)| sum + p);
+        let mut pick = rng.gen::<f32>() * total;
+        let mut chosen = remaining.len() - 1;
+        
+        for (j, &(_, p)) in remaining.iter().enumerate() {
+            if pick < p {
+                chosen = j;
+                break;
+            }
+            pick -= p;
+        }
+        
+        moves.push(remaining.remove(chosen).0);
+    }
+    
+    moves
+}
+
 impl fmt::Debug for AiPlayer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "AiPlayer {{ wins: {}, loses: {}, ties: {}, mistakes: {} }}", 
@@ -92,14 +179,19 @@ impl fmt::Debug for AiPlayer {
 
 impl Individual for AiPlayer {
 
-    fn new() -> AiPlayer {
+    type Config = AiConfig;
+
+    fn new<R: Rng>(config: &AiConfig, rng: &mut R) -> AiPlayer {
         AiPlayer {
-            neural_net: Network::with_dimensions(&[9, 9, 9]),
+            neural_net: Network::with_activations(&[9, 9, 9],
+                                                  &[Activation::Sigmoid, Activation::Softmax],
+                                                  rng),
             mark: board::Mark::None,
-            wins: 0u16,
-            loses: 0u16,
-            ties: 0u16,
-            mistakes: 0u16,
+            wins: 0u32,
+            loses: 0u32,
+            ties: 0u32,
+            mistakes: 0u32,
+            rated: config.rating.map(Rated::new),
         }
     }
     
@@ -110,23 +202,165 @@ impl Individual for AiPlayer {
         self.mistakes = 0;
     }
 
+    fn conclude(&mut self) {
+        // Each generation is a rating period
+        if let Some(ref mut rated) = self.rated {
+            rated.conclude();
+        }
+    }
+
     fn get_rating(&self) -> i32 {
+        // Only a full Glicko-2 update while the period's games are pending;
+        // once concluded it just reads the rating
+        if let Some(ref rated) = self.rated {
+            return rated.current().rating.round() as i32;
+        }
+
         self.wins as i32 + self.ties as i32 - self.loses as i32 - (self.mistakes as i32 / 4)
     }
 
-    fn reproduce(&self, partner: &AiPlayer) -> AiPlayer {
+    fn record(&self) -> Record {
+        Record {
+            wins: self.wins,
+            ties: self.ties,
+            losses: self.loses,
+            mistakes: self.mistakes,
+        }
+    }
+
+    fn reproduce<R: Rng>(&self, partner: &AiPlayer, config: &AiConfig, rng: &mut R) -> AiPlayer {
         return AiPlayer {
-            neural_net: self.neural_net.reproduce(&partner.neural_net),
+            neural_net: self.neural_net.reproduce(&partner.neural_net,
+                                                  config.crossover,
+                                                  &config.mutation,
+                                                  rng),
             mark: board::Mark::None,
-            wins: 0u16,
-            loses: 0u16,
-            ties: 0u16,
-            mistakes: 0u16,
+            wins: 0u32,
+            loses: 0u32,
+            ties: 0u32,
+            mistakes: 0u32,
+            rated: config.rating.map(Rated::new),
         }
     }
     
-    fn compete(&mut self, other: &mut AiPlayer) {
-        play_game(self, other);
-        play_game(other, self);
+    fn distance(&self, other: &AiPlayer) -> f64 {
+        self.neural_net.distance(&other.neural_net) as f64
     }
-}
\ No newline at end of file
+
+    fn compete<R: Rng>(&mut self, other: &mut AiPlayer, rng: &mut R) {
+        // Both games are rated against the ratings the period started with
+        let ratings = (self.rated.as_ref().map(|r| r.rating), other.rated.as_ref().map(|r| r.rating));
+
+        let first = play_game(self, other, rng).score();
+        let second = 1f64 - play_game(other, self, rng).score();
+
+        if let (Some(mine), Some(theirs)) = ratings {
+            for &score in &[first, second] {
+                self.rated.as_mut().unwrap().record(theirs, score);
+                other.rated.as_mut().unwrap().record(mine, 1f64 - score);
+            }
+        }
+    }
+}
+
+impl Evaluate for AiPlayer {
+
+    type Environment = AiEnvironment;
+
+    fn evaluate<R: Rng>(&mut self, environment: &AiEnvironment, rng: &mut R) {
+        let is_softmax = is_softmax(&self.neural_net);
+
+        match *environment {
+            AiEnvironment::Examples(ref examples) => {
+                SCRATCH.with(|scratch| {
+                    let mut scratch = scratch.borrow_mut();
+
+                    for example in examples {
+                        let outputs = self.neural_net.evaluate(&example.inputs, &mut scratch);
+                        let choice = move_order(outputs, is_softmax, rng)[0];
+
+                        // Any of several equally good labelled moves counts
+                        let best = example.outputs[choice] == example.outputs[best_index(&example.outputs)];
+                        self.score_choice(best, example.inputs[choice] != 0f32);
+                    }
+                });
+            }
+            AiEnvironment::Coverage(ref coverage) => {
+                SCRATCH.with(|scratch| {
+                    let mut scratch = scratch.borrow_mut();
+
+                    for position in coverage.positions() {
+                        let choice = position.choice(&self.neural_net, &mut scratch, rng);
+
+                        self.score_choice(position.optimal.contains(&choice),
+                                          position.cells[choice] != board::Mark::None);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Returns the index of the largest value
+pub fn best_index(values: &[f32]) -> usize {
+    values.iter()
+        .enumerate()
+        .fold((0, ::std::f32::MIN), |best, (i, &value)| if value > best.1 { (i, value) } else { best })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    #[test]
+    fn test_examples_accept_any_best_move() {
+        let mut rng = StdRng::from_seed(&[4]);
+        let network = Network::with_activations(&[9, 9, 9], &[Activation::Linear; 2], &mut rng);
+        let mut player = AiPlayer::with_network(network);
+        let inputs = vec![0f32; 9];
+        let choice = best_index(&player.neural_net.run(&inputs).pop().unwrap());
+
+        // Another move shares the best label with the player's
+        let mut outputs = vec![0f32; 9];
+        outputs[choice] = 0.5;
+        outputs[if choice == 0 { 8 } else { 0 }] = 0.5;
+
+        let examples = vec![Example { inputs: inputs, outputs: outputs }];
+        player.evaluate(&AiEnvironment::Examples(examples), &mut rng);
+
+        assert_eq!((1, 0, 0), (player.wins, player.loses, player.mistakes));
+    }
+
+    #[test]
+    fn test_move_order() {
+        let mut rng = StdRng::from_seed(&[4]);
+
+        // A large negative output is tried last, not first
+        assert_eq!(vec![2, 0, 1], move_order(&[0.1, -5.0, 0.3], false, &mut rng));
+
+        // Probabilities are sampled, so a certain move always comes first
+        for _ in 0..10 {
+            assert_eq!(1, move_order(&[0.0, 1.0, 0.0], true, &mut rng)[0]);
+        }
+    }
+
+    #[test]
+    fn test_conclude_settles_rating() {
+        let mut rng = StdRng::from_seed(&[4]);
+        let config = AiConfig { rating: Some(RatingSystem::Glicko2 { tau: 0.5 }), ..AiConfig::default() };
+        let mut player = AiPlayer::new(&config, &mut rng);
+
+        player.rated.as_mut().unwrap().record(Default::default(), 1f64);
+        let pending = player.get_rating();
+        assert!(pending > 1500);
+
+        player.conclude();
+        assert_eq!(pending, player.get_rating());
+
+        // Resetting the counters leaves the settled rating alone
+        player.reset();
+        assert_eq!(pending, player.get_rating());
+    }
+}
@@ -21,6 +21,62 @@ impl fmt::Display for Mark {
     }
 }
 
+impl Mark {
+    /// Returns the other player's mark, or `None` for `None`
+    pub fn opponent(&self) -> Mark {
+        match *self {
+            Mark::X => Mark::O,
+            Mark::O => Mark::X,
+            Mark::None => Mark::None,
+        }
+    }
+}
+
+/// The rows, columns and diagonals of the board
+pub const LINES: [[usize; 3]; 8] = [[0, 1, 2], [3, 4, 5], [6, 7, 8],
+                                    [0, 3, 6], [1, 4, 7], [2, 5, 8],
+                                    [0, 4, 8], [2, 4, 6]];
+
+/// Returns the indices of the empty spaces
+pub fn empty_spaces(cells: &[Mark; 9]) -> Vec<usize> {
+    (0..9).filter(|&i| cells[i] == Mark::None).collect()
+}
+
+/// Returns whether either player has three in a line
+pub fn is_won(cells: &[Mark; 9]) -> bool {
+    LINES.iter().any(|line| {
+        cells[line[0]] != Mark::None && cells[line[1]] == cells[line[0]] && cells[line[2]] == cells[line[0]]
+    })
+}
+
+/// Encodes the marks as network inputs from the point of view of `mark`: -1
+/// for its own marks, 1 for its opponent's and 0 for empty spaces
+pub fn inputs(cells: &[Mark; 9], mark: Mark) -> [f32; 9] {
+    let mut inputs = [0f32; 9];
+
+    for i in 0..9 {
+        if cells[i] == mark {
+            inputs[i] = -1f32;
+        } else if cells[i] != Mark::None {
+            inputs[i] = 1f32;
+        }
+    }
+    inputs
+}
+
+/// Identifies a position by its marks in base 3 and the player to move
+pub fn key(cells: &[Mark; 9], to_move: Mark) -> u32 {
+    let position = cells.iter().fold(0, |key, &mark| {
+        key * 3 + match mark {
+            Mark::None => 0,
+            Mark::X => 1,
+            Mark::O => 2,
+        }
+    });
+
+    position * 2 + if to_move == Mark::X { 0 } else { 1 }
+}
+
 /// A standard Tic-Tac-Toe game board which is indexed like so:
 ///
 /// 0 | 1 | 2
@@ -52,6 +108,18 @@ pub struct View<'a> {
     board: &'a mut Board,
 }
 
+impl<'a> View<'a> {
+
+    /// Copies the marks as seen from this view
+    pub fn cells(&self) -> [Mark; 9] {
+        let mut cells = [Mark::None; 9];
+        for i in 0..9 {
+            cells[i] = self[i];
+        }
+        cells
+    }
+}
+
 impl<'a> Index<usize> for View<'a> {
     type Output = Mark;
 
@@ -151,4 +219,20 @@ mod tests {
         
         assert_eq!(Mark::O, board.0[6]);
     }
+
+    #[test]
+    fn cells_is_won() {
+        let mut board = Board::new();
+        board.0[0] = Mark::X;
+        board.0[4] = Mark::X;
+        board.0[8] = Mark::X;
+
+        let cells = board.get_view(Rotation::Left).cells();
+        assert_eq!(Mark::X, cells[2]);
+        assert!(is_won(&cells));
+        assert_eq!(vec![0, 1, 3, 5, 7, 8], empty_spaces(&cells));
+
+        board.0[8] = Mark::O;
+        assert!(!is_won(&board.get_view(Rotation::Bottom).cells()));
+    }
 }
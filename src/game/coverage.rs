@@ -0,0 +1,183 @@
+//! Scores networks on every position a game can reach.
+//!
+//! Tic-Tac-Toe is small enough to enumerate every reachable position where
+//! the game isn't over. Positions that are rotations or reflections of each
+//! other are only kept once, leaving 627. Each is labelled with its optimal
+//! moves by minimax, so a network can be scored by how often it picks one.
+//!
+//! A move is optimal if it keeps the position's outcome with perfect play.
+//! A win that takes longer than the quickest one still counts.
+
+use std::collections::HashSet;
+use neural::{Network, Scratch};
+use rand::Rng;
+use super::MinimaxPlayer;
+use super::ai::{is_softmax, move_order};
+use super::board::{Mark, empty_spaces, inputs, is_won, key};
+
+/// The rotations and reflections of the board, as the index each space is
+/// taken from
+const SYMMETRIES: [[usize; 9]; 8] = [[0, 1, 2, 3, 4, 5, 6, 7, 8],
+                                     [6, 3, 0, 7, 4, 1, 8, 5, 2],
+                                     [8, 7, 6, 5, 4, 3, 2, 1, 0],
+                                     [2, 5, 8, 1, 4, 7, 0, 3, 6],
+                                     [2, 1, 0, 5, 4, 3, 8, 7, 6],
+                                     [6, 7, 8, 3, 4, 5, 0, 1, 2],
+                                     [0, 3, 6, 1, 4, 7, 2, 5, 8],
+                                     [8, 5, 2, 7, 4, 1, 6, 3, 0]];
+
+/// A position and the moves that play it perfectly
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub cells: [Mark; 9],
+    pub to_move: Mark,
+    pub optimal: Vec<usize>,
+}
+
+impl Position {
+
+    /// Returns the move an `AiPlayer` with the network would try first,
+    /// which may be an occupied space
+    pub fn choice<R: Rng>(&self, network: &Network, scratch: &mut Scratch, rng: &mut R) -> usize {
+        let outputs = network.evaluate(&inputs(&self.cells, self.to_move), scratch);
+        move_order(outputs, is_softmax(network), rng)[0]
+    }
+
+    /// Returns whether the network's first choice is an optimal move.
+    /// Picking an occupied space never is.
+    pub fn is_solved_by<R: Rng>(&self, network: &Network, scratch: &mut Scratch, rng: &mut R) -> bool {
+        self.optimal.contains(&self.choice(network, scratch, rng))
+    }
+}
+
+/// Every distinct reachable position where the game isn't over
+pub struct Coverage {
+    positions: Vec<Position>,
+}
+
+impl Coverage {
+
+    /// Enumerates the positions and solves each with minimax
+    pub fn new() -> Coverage {
+        let mut coverage = Coverage { positions: Vec::new() };
+        let mut seen = HashSet::new();
+        let mut minimax = MinimaxPlayer::new();
+
+        coverage.enumerate(&mut [Mark::None; 9], Mark::X, &mut seen, &mut minimax);
+        coverage
+    }
+
+    fn enumerate(&mut self,
+                 cells: &mut [Mark; 9],
+                 to_move: Mark,
+                 seen: &mut HashSet<u32>,
+                 minimax: &mut MinimaxPlayer) {
+        let spaces = empty_spaces(cells);
+
+        // The subtree of a symmetric position is symmetric, so it can be
+        // skipped along with the position
+        if is_won(cells) || spaces.is_empty() || !seen.insert(canonical_key(cells, to_move)) {
+            return;
+        }
+
+        self.positions.push(Position {
+            cells: *cells,
+            to_move: to_move,
+            optimal: minimax.sound_moves(cells, to_move),
+        });
+
+        for i in spaces {
+            cells[i] = to_move;
+            self.enumerate(cells, to_move.opponent(), seen, minimax);
+            cells[i] = Mark::None;
+        }
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    /// Returns the number of positions where the network picks an optimal
+    /// move
+    pub fn solved<R: Rng>(&self, network: &Network, scratch: &mut Scratch, rng: &mut R) -> usize {
+        self.positions.iter()
+            .filter(|position| position.is_solved_by(network, scratch, rng))
+            .count()
+    }
+
+    /// Returns the fraction of positions where the network picks an optimal
+    /// move
+    pub fn score<R: Rng>(&self, network: &Network, rng: &mut R) -> f64 {
+        self.solved(network, &mut Scratch::new(), rng) as f64 / self.positions.len() as f64
+    }
+}
+
+/// Returns the smallest key of any rotation or reflection of the position
+fn canonical_key(cells: &[Mark; 9], to_move: Mark) -> u32 {
+    SYMMETRIES.iter()
+        .map(|symmetry| {
+            let mut transformed = [Mark::None; 9];
+            for i in 0..9 {
+                transformed[i] = cells[symmetry[i]];
+            }
+            key(&transformed, to_move)
+        })
+        .min()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::board::Mark::{X, O};
+    use neural::Activation;
+    use rand::{SeedableRng, StdRng};
+
+    const N: Mark = Mark::None;
+
+    #[test]
+    fn test_canonical_key() {
+        let corner = [X, N, N, N, N, N, N, N, N];
+        let other_corner = [N, N, N, N, N, N, N, N, X];
+        let edge = [N, X, N, N, N, N, N, N, N];
+
+        assert_eq!(canonical_key(&corner, O), canonical_key(&other_corner, O));
+        assert!(canonical_key(&corner, O) != canonical_key(&edge, O));
+    }
+
+    #[test]
+    fn test_positions() {
+        let coverage = Coverage::new();
+        let positions = coverage.positions();
+
+        assert_eq!(627, positions.len());
+
+        // Every opening ties, but only the centre answers a corner
+        assert_eq!((0..9).collect::<Vec<_>>(), positions[0].optimal);
+        assert_eq!(vec![4], positions[1].optimal);
+        assert_eq!([X, N, N, N, N, N, N, N, N], positions[1].cells);
+    }
+
+    #[test]
+    fn test_score() {
+        let coverage = Coverage::new();
+        let mut rng = StdRng::from_seed(&[5]);
+
+        // Ignores the board and always picks the centre
+        let mut network = Network::with_activations(&[9, 9, 9], &[Activation::Linear; 2], &mut rng);
+        for weight in network.layers[1].weights.iter_mut() {
+            *weight = 0f32;
+        }
+        for (i, bias) in network.layers[1].biases.iter_mut().enumerate() {
+            *bias = if i == 4 { 1f32 } else { 0f32 };
+        }
+
+        let centre_optimal = coverage.positions().iter()
+            .filter(|position| position.optimal.contains(&4))
+            .count();
+
+        assert!(centre_optimal > 0);
+        assert_eq!(centre_optimal, coverage.solved(&network, &mut Scratch::new(), &mut rng));
+        assert_eq!(centre_optimal as f64 / 627f64, coverage.score(&network, &mut rng));
+    }
+}
@@ -0,0 +1,122 @@
+use rand::{Rng, SeedableRng, StdRng};
+use super::{board, Player, GameResult};
+use super::board::{Mark, LINES, empty_spaces};
+
+const CENTRE: usize = 4;
+const CORNERS: [usize; 4] = [0, 2, 6, 8];
+
+/// Plays by simple rules, in order: win if possible, block the opponent's
+/// win, take the centre, take a corner, then take any space. Ties between
+/// spaces are broken randomly.
+pub struct HeuristicPlayer {
+    mark: Mark,
+    rng: StdRng,
+}
+
+impl HeuristicPlayer {
+
+    /// Constructs a `HeuristicPlayer` whose ties are broken by a generator
+    /// seeded with `seed`, independently of the game's
+    pub fn new(seed: usize) -> HeuristicPlayer {
+        HeuristicPlayer {
+            mark: Mark::None,
+            rng: StdRng::from_seed(&[seed]),
+        }
+    }
+
+    /// Returns the spaces the rules choose between
+    fn candidates(&self, cells: &[Mark; 9]) -> Vec<usize> {
+        let wins = completing_spaces(cells, self.mark);
+        if !wins.is_empty() {
+            return wins;
+        }
+
+        let blocks = completing_spaces(cells, self.mark.opponent());
+        if !blocks.is_empty() {
+            return blocks;
+        }
+
+        if cells[CENTRE] == Mark::None {
+            return vec![CENTRE];
+        }
+
+        let corners = CORNERS.iter().cloned().filter(|&i| cells[i] == Mark::None).collect::<Vec<_>>();
+        if !corners.is_empty() {
+            return corners;
+        }
+
+        empty_spaces(cells)
+    }
+}
+
+impl Player for HeuristicPlayer {
+
+    fn set_mark(&mut self, mark: Mark) {
+        self.mark = mark;
+    }
+
+    fn play<R: Rng>(&mut self, board: &mut board::View, _rng: &mut R) {
+        let candidates = self.candidates(&board.cells());
+
+        board[*self.rng.choose(&candidates).unwrap()] = self.mark;
+    }
+
+    fn game_result(&mut self, _result: GameResult) {}
+}
+
+/// Returns the empty spaces that would give `mark` three in a line
+fn completing_spaces(cells: &[Mark; 9], mark: Mark) -> Vec<usize> {
+    let mut spaces = LINES.iter()
+        .filter_map(|line| {
+            let marked = line.iter().filter(|&&i| cells[i] == mark).count();
+            let empty = line.iter().cloned().find(|&i| cells[i] == Mark::None);
+
+            if marked == 2 { empty } else { None }
+        })
+        .collect::<Vec<_>>();
+
+    spaces.sort();
+    spaces.dedup();
+    spaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::board::Mark::{X, O};
+
+    const N: Mark = Mark::None;
+
+    fn player(mark: Mark) -> HeuristicPlayer {
+        let mut player = HeuristicPlayer::new(0);
+        player.set_mark(mark);
+        player
+    }
+
+    #[test]
+    fn test_wins_before_blocking() {
+        assert_eq!(vec![5], player(X).candidates(&[O, O, N,
+                                                    X, X, N,
+                                                    N, N, N]));
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(vec![2], player(X).candidates(&[O, O, N,
+                                                    X, N, N,
+                                                    N, N, N]));
+    }
+
+    #[test]
+    fn test_centre_then_corners() {
+        assert_eq!(vec![4], player(O).candidates(&[X, N, N,
+                                                    N, N, N,
+                                                    N, N, N]));
+        assert_eq!(vec![2, 6, 8], player(X).candidates(&[O, N, N,
+                                                          N, X, N,
+                                                          N, N, N]));
+        assert_eq!(vec![3, 5], player(O).candidates(&[O, X, O,
+                                                       N, X, N,
+                                                       X, O, X]));
+    }
+}
@@ -1,6 +1,7 @@
 use super::{Player, GameResult};
 use std::io::{BufRead, stdin};
 use super::board;
+use rand::Rng;
 
 pub struct HumanPlayer {
     mark: board::Mark,
@@ -19,7 +20,7 @@ impl Player for HumanPlayer {
         self.mark = mark;
     }   
 
-    fn play(&mut self, board: &mut board::View) {
+    fn play<R: Rng>(&mut self, board: &mut board::View, _rng: &mut R) {
         println!("*****");
         println!("{}|{}|{}   0|1|2", board[0], board[1], board[2]);
         println!("-+-+-   -+-+-");
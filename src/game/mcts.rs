@@ -0,0 +1,330 @@
+use std::f64;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use neural::{Network, Scratch};
+use rand::Rng;
+use super::{board, Player, GameResult};
+use super::board::{Mark, empty_spaces, inputs, is_won};
+
+/// The weight every empty space keeps in a network guided rollout, so that
+/// no move is ruled out entirely
+const MIN_WEIGHT: f32 = 0.001;
+
+/// How long a search may run
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Budget {
+    /// A fixed number of playouts
+    Playouts(u32),
+    /// As many playouts as fit in the time, but at least one
+    Time(Duration),
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    /// Parses a number of playouts, such as `5000`, or a time in
+    /// milliseconds, such as `200ms`
+    fn from_str(s: &str) -> Result<Budget, String> {
+        if s.ends_with("ms") {
+            s.trim_end_matches("ms")
+                .parse()
+                .map(|ms| Budget::Time(Duration::from_millis(ms)))
+                .map_err(|_| format!("invalid time in '{}'", s))
+        } else {
+            match s.parse() {
+                Ok(0) => Err("a search needs at least 1 playout".to_string()),
+                Ok(playouts) => Ok(Budget::Playouts(playouts)),
+                Err(_) => Err(format!("invalid budget '{}'", s)),
+            }
+        }
+    }
+}
+
+/// A position in the search tree
+struct Node {
+    cells: [Mark; 9],
+    /// The player who moved into this position
+    mover: Mark,
+    parent: Option<usize>,
+    children: Vec<(usize, usize)>,
+    untried: Vec<usize>,
+    visits: u32,
+    /// The points `mover` won in playouts through this position, a tie
+    /// worth half a win
+    score: f64,
+}
+
+impl Node {
+    fn new(cells: [Mark; 9], mover: Mark, parent: Option<usize>) -> Node {
+        let untried = if is_won(&cells) { Vec::new() } else { empty_spaces(&cells) };
+
+        Node {
+            cells: cells,
+            mover: mover,
+            parent: parent,
+            children: Vec::new(),
+            untried: untried,
+            visits: 0,
+            score: 0f64,
+        }
+    }
+}
+
+/// Plays by Monte Carlo tree search, choosing which moves to explore with
+/// UCT and valuing new positions by playing them out. The playouts are
+/// random unless a network is given, in which case it picks their moves.
+pub struct MctsPlayer {
+    mark: Mark,
+    budget: Budget,
+    exploration: f64,
+    network: Option<Network>,
+    scratch: Scratch,
+}
+
+impl MctsPlayer {
+
+    /// Constructs an `MctsPlayer` that runs `playouts` random playouts a move
+    pub fn new(playouts: u32) -> MctsPlayer {
+        MctsPlayer {
+            mark: Mark::None,
+            budget: Budget::Playouts(playouts),
+            exploration: f64::consts::SQRT_2,
+            network: None,
+            scratch: Scratch::new(),
+        }
+    }
+
+    /// Sets how long each search may run
+    pub fn budget(mut self, budget: Budget) -> MctsPlayer {
+        self.budget = budget;
+        self
+    }
+
+    /// Sets the UCT exploration constant. Higher values try weaker looking
+    /// moves more often.
+    pub fn exploration(mut self, exploration: f64) -> MctsPlayer {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Uses a network's outputs, read like `AiPlayer` reads them, as the
+    /// probabilities of each move in the playouts
+    pub fn network(mut self, network: Network) -> MctsPlayer {
+        self.network = Some(network);
+        self
+    }
+
+    /// Returns the move with the most visits after searching from `cells`
+    fn search<R: Rng>(&mut self, cells: [Mark; 9], rng: &mut R) -> usize {
+        let mut tree = vec![Node::new(cells, self.mark.opponent(), None)];
+        let started = Instant::now();
+        let mut playouts = 0;
+
+        loop {
+            let done = match self.budget {
+                Budget::Playouts(limit) => playouts >= limit,
+                Budget::Time(limit) => playouts > 0 && started.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+
+            let leaf = self.select(&mut tree, rng);
+            let winner = self.play_out(tree[leaf].cells, tree[leaf].mover.opponent(), rng);
+            backpropagate(&mut tree, leaf, winner);
+
+            playouts += 1;
+        }
+
+        tree[0].children.iter()
+            .max_by_key(|&&(_, child)| tree[child].visits)
+            .map(|&(space, _)| space)
+            .unwrap_or_else(|| empty_spaces(&cells)[0])
+    }
+
+    /// Descends by UCT until reaching a node with untried moves, expands
+    /// one of them and returns the new node. Returns the node reached if the
+    /// game is over there.
+    fn select<R: Rng>(&self, tree: &mut Vec<Node>, rng: &mut R) -> usize {
+        let mut current = 0;
+
+        while tree[current].untried.is_empty() && !tree[current].children.is_empty() {
+            let parent_visits = (tree[current].visits as f64).ln();
+
+            current = tree[current].children.iter()
+                .map(|&(_, child)| {
+                    let node = &tree[child];
+                    let uct = node.score / node.visits as f64 +
+                        self.exploration * (parent_visits / node.visits as f64).sqrt();
+                    (child, uct)
+                })
+                .fold((current, f64::NEG_INFINITY), |best, candidate| {
+                    if candidate.1 > best.1 { candidate } else { best }
+                })
+                .0;
+        }
+
+        if tree[current].untried.is_empty() {
+            return current;
+        }
+
+        let len = tree[current].untried.len();
+        let space = tree[current].untried.swap_remove(rng.gen_range(0, len));
+        let mover = tree[current].mover.opponent();
+        let mut cells = tree[current].cells;
+        cells[space] = mover;
+
+        tree.push(Node::new(cells, mover, Some(current)));
+        let child = tree.len() - 1;
+        tree[current].children.push((space, child));
+        child
+    }
+
+    /// Plays the game out and returns the winner, or `None` for a tie
+    fn play_out<R: Rng>(&mut self, mut cells: [Mark; 9], mut to_move: Mark, rng: &mut R) -> Mark {
+        loop {
+            if is_won(&cells) {
+                return to_move.opponent();
+            }
+
+            let spaces = empty_spaces(&cells);
+            if spaces.is_empty() {
+                return Mark::None;
+            }
+
+            let space = match self.network {
+                Some(ref network) => {
+                    let outputs = network.evaluate(&inputs(&cells, to_move), &mut self.scratch);
+                    let weights = spaces.iter()
+                        .map(|&i| outputs[i].max(0f32) + MIN_WEIGHT)
+                        .collect::<Vec<_>>();
+                    spaces[weighted_choice(&weights, rng)]
+                }
+                None => *rng.choose(&spaces).unwrap(),
+            };
+
+            cells[space] = to_move;
+            to_move = to_move.opponent();
+        }
+    }
+}
+
+impl Player for MctsPlayer {
+
+    fn set_mark(&mut self, mark: Mark) {
+        self.mark = mark;
+    }
+
+    fn play<R: Rng>(&mut self, board: &mut board::View, rng: &mut R) {
+        let space = self.search(board.cells(), rng);
+
+        board[space] = self.mark;
+    }
+
+    fn game_result(&mut self, _result: GameResult) {}
+}
+
+/// Credits the playout's result to every node from `leaf` up to the root
+fn backpropagate(tree: &mut [Node], leaf: usize, winner: Mark) {
+    let mut current = Some(leaf);
+
+    while let Some(i) = current {
+        let node = &mut tree[i];
+
+        node.visits += 1;
+        node.score += if winner == node.mover {
+            1f64
+        } else if winner == Mark::None {
+            0.5
+        } else {
+            0f64
+        };
+
+        current = node.parent;
+    }
+}
+
+/// Returns an index chosen with probability proportional to its weight
+fn weighted_choice<R: Rng>(weights: &[f32], rng: &mut R) -> usize {
+    let total = weights.iter().fold(0f32, |sum, w| sum + w);
+    let mut pick = rng.gen::<f32>() * total;
+
+    for (i, &weight) in weights.iter().enumerate() {
+        if pick < weight {
+            return i;
+        }
+        pick -= weight;
+    }
+
+    weights.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::board::Mark::{X, O};
+    use neural::Activation;
+    use rand::{SeedableRng, StdRng};
+
+    const N: Mark = Mark::None;
+
+    fn search(player: MctsPlayer, mark: Mark, cells: [Mark; 9]) -> usize {
+        let mut player = player;
+        let mut rng = StdRng::from_seed(&[6]);
+
+        player.set_mark(mark);
+        player.search(cells, &mut rng)
+    }
+
+    #[test]
+    fn test_takes_win() {
+        assert_eq!(2, search(MctsPlayer::new(500), X, [X, X, N,
+                                                       O, O, N,
+                                                       N, N, N]));
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(2, search(MctsPlayer::new(2000), X, [O, O, N,
+                                                        X, N, N,
+                                                        N, N, X]));
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let player = MctsPlayer::new(0).budget(Budget::Time(Duration::from_millis(10)));
+
+        assert_eq!(8, search(player, O, [X, O, X,
+                                         X, O, O,
+                                         O, X, N]));
+    }
+
+    #[test]
+    fn test_network_rollouts() {
+        let mut rng = StdRng::from_seed(&[1]);
+        let network = Network::with_activations(&[9, 9, 9], &[Activation::Tanh, Activation::Softmax], &mut rng);
+        let player = MctsPlayer::new(2000).network(network);
+
+        assert_eq!(2, search(player, X, [O, O, N,
+                                         X, N, N,
+                                         N, N, X]));
+    }
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!(Ok(Budget::Playouts(5000)), "5000".parse());
+        assert_eq!(Ok(Budget::Time(Duration::from_millis(200))), "200ms".parse());
+        assert!("0".parse::<Budget>().is_err());
+        assert!("fast".parse::<Budget>().is_err());
+        assert!("-5ms".parse::<Budget>().is_err());
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let mut rng = StdRng::from_seed(&[2]);
+
+        for _ in 0..20 {
+            assert_eq!(1, weighted_choice(&[0f32, 1f32, 0f32], &mut rng));
+        }
+    }
+}
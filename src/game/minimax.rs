@@ -0,0 +1,281 @@
+use std::collections::{HashMap, HashSet};
+use std::i32;
+use super::{board, Player, GameResult};
+use super::board::{Mark, empty_spaces, inputs, is_won, key};
+use neural::Example;
+use rand::Rng;
+
+/// The value of a win, to which the number of empty spaces left is added so
+/// that quicker wins and slower losses are preferred
+const WIN: i32 = 100;
+
+#[derive(Copy, Clone)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// A value found by an earlier search of a position
+#[derive(Copy, Clone)]
+struct Entry {
+    value: i32,
+    depth: u32,
+    bound: Bound,
+}
+
+/// Plays by searching the game tree with minimax and alpha-beta pruning.
+/// With the full depth it never loses.
+pub struct MinimaxPlayer {
+    mark: Mark,
+    depth: u32,
+    randomised: bool,
+    table: HashMap<u32, Entry>,
+}
+
+impl MinimaxPlayer {
+
+    /// Constructs a perfect `MinimaxPlayer` that always takes the first of
+    /// equally good moves
+    pub fn new() -> MinimaxPlayer {
+        MinimaxPlayer {
+            mark: Mark::None,
+            depth: 9,
+            randomised: false,
+            table: HashMap::new(),
+        }
+    }
+
+    /// Limits how many moves ahead are searched. Positions that aren't
+    /// decided within the limit are valued as ties.
+    pub fn depth(mut self, depth: u32) -> MinimaxPlayer {
+        self.depth = depth;
+        self.table.clear();
+        self
+    }
+
+    /// Chooses randomly between equally good moves
+    pub fn randomised(mut self, randomised: bool) -> MinimaxPlayer {
+        self.randomised = randomised;
+        self
+    }
+
+    /// Generates an example for every position reachable in a game that
+    /// isn't already over, as seen by the player to move. The inputs are
+    /// encoded like `AiPlayer`'s, -1 for the player's own marks and 1 for the
+    /// opponent's, and the outputs share a total of 1 between the best moves.
+    pub fn examples(&mut self) -> Vec<Example> {
+        let mut examples = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cells = [Mark::None; 9];
+
+        self.collect_examples(&mut cells, Mark::X, &mut seen, &mut examples);
+        examples
+    }
+
+    fn collect_examples(&mut self,
+                        cells: &mut [Mark; 9],
+                        to_move: Mark,
+                        seen: &mut HashSet<u32>,
+                        examples: &mut Vec<Example>) {
+        if is_won(cells) || empty_spaces(cells).is_empty() || !seen.insert(key(cells, to_move)) {
+            return;
+        }
+
+        let best = self.best_moves(cells, to_move);
+        let outputs = (0..9)
+            .map(|i| if best.contains(&i) { 1f32 / best.len() as f32 } else { 0f32 })
+            .collect();
+
+        examples.push(Example { inputs: inputs(cells, to_move).to_vec(), outputs: outputs });
+
+        for i in empty_spaces(cells) {
+            cells[i] = to_move;
+            self.collect_examples(cells, to_move.opponent(), seen, examples);
+            cells[i] = Mark::None;
+        }
+    }
+
+    /// Returns every move for `to_move` that is as good as the best one.
+    /// Quicker wins and slower losses count as better.
+    pub fn best_moves(&mut self, cells: &[Mark; 9], to_move: Mark) -> Vec<usize> {
+        self.moves_by(cells, to_move, |value| value)
+    }
+
+    /// Returns every move for `to_move` that keeps the best outcome, whether
+    /// a win, a tie or a loss, however long it takes to reach
+    pub fn sound_moves(&mut self, cells: &[Mark; 9], to_move: Mark) -> Vec<usize> {
+        self.moves_by(cells, to_move, i32::signum)
+    }
+
+    /// Returns the moves whose values, as ranked by `rank`, are the highest
+    fn moves_by<F: Fn(i32) -> i32>(&mut self, cells: &[Mark; 9], to_move: Mark, rank: F) -> Vec<usize> {
+        let mut cells = *cells;
+        let mut best = i32::MIN;
+        let mut moves = Vec::new();
+
+        // Each move is searched with the full window so that ties are exact
+        for i in empty_spaces(&cells) {
+            cells[i] = to_move;
+            let value = rank(-self.negamax(&mut cells, to_move.opponent(), self.depth.saturating_sub(1),
+                                           -i32::MAX, i32::MAX));
+            cells[i] = Mark::None;
+
+            if value > best {
+                best = value;
+                moves.clear();
+            }
+            if value == best {
+                moves.push(i);
+            }
+        }
+
+        moves
+    }
+
+    /// Returns the value of the position for the player to move
+    fn negamax(&mut self, cells: &mut [Mark; 9], to_move: Mark, depth: u32, mut alpha: i32, mut beta: i32) -> i32 {
+        let empty = empty_spaces(cells);
+
+        // The previous move can only have won for the opponent
+        if is_won(cells) {
+            return -(WIN + empty.len() as i32);
+        }
+        if empty.is_empty() || depth == 0 {
+            return 0;
+        }
+
+        let key = key(cells, to_move);
+        let original_alpha = alpha;
+
+        if let Some(entry) = self.table.get(&key) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.value,
+                    Bound::Lower => alpha = alpha.max(entry.value),
+                    Bound::Upper => beta = beta.min(entry.value),
+                }
+
+                if alpha >= beta {
+                    return entry.value;
+                }
+            }
+        }
+
+        let mut best = -i32::MAX;
+
+        for i in empty {
+            cells[i] = to_move;
+            let value = -self.negamax(cells, to_move.opponent(), depth - 1, -beta, -alpha);
+            cells[i] = Mark::None;
+
+            best = best.max(value);
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.insert(key, Entry { value: best, depth: depth, bound: bound });
+        best
+    }
+}
+
+impl Player for MinimaxPlayer {
+
+    fn set_mark(&mut self, mark: Mark) {
+        self.mark = mark;
+    }
+
+    fn play<R: Rng>(&mut self, board: &mut board::View, rng: &mut R) {
+        let moves = self.best_moves(&board.cells(), self.mark);
+        let choice = if self.randomised {
+            *rng.choose(&moves).unwrap()
+        } else {
+            moves[0]
+        };
+
+        board[choice] = self.mark;
+    }
+
+    fn game_result(&mut self, _result: GameResult) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::board::Mark::{X, O};
+    use super::super::{play_game, GameResult};
+    use rand::{SeedableRng, StdRng};
+
+    const N: Mark = Mark::None;
+
+    #[test]
+    fn test_takes_win() {
+        let mut player = MinimaxPlayer::new();
+
+        // X can win at 2, or lose by letting O take 5
+        assert_eq!(vec![2], player.best_moves(&[X, X, N,
+                                                O, O, N,
+                                                N, N, N], X));
+    }
+
+    #[test]
+    fn test_sound_moves() {
+        let mut player = MinimaxPlayer::new();
+        let cells = [X, X, N,
+                     N, O, N,
+                     N, N, O];
+
+        // Forking at 3 or 6 wins later instead of at once
+        assert_eq!(vec![2], player.best_moves(&cells, X));
+        assert_eq!(vec![2, 3, 6], player.sound_moves(&cells, X));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let mut player = MinimaxPlayer::new().depth(2);
+
+        assert_eq!(vec![2], player.best_moves(&[O, O, N,
+                                                X, N, N,
+                                                N, N, X], X));
+    }
+
+    #[test]
+    fn test_perfect_play_ties() {
+        let mut rng = StdRng::from_seed(&[7]);
+        let mut first = MinimaxPlayer::new().randomised(true);
+        let mut second = MinimaxPlayer::new().randomised(true);
+
+        for _ in 0..10 {
+            match play_game(&mut first, &mut second, &mut rng) {
+                GameResult::Tie => {}
+                _ => panic!("perfect players shouldn't win"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        let examples = MinimaxPlayer::new().examples();
+
+        // Every opening ties with perfect play
+        assert_eq!(vec![0f32; 9], examples[0].inputs);
+        assert_eq!(vec![1f32 / 9f32; 9], examples[0].outputs);
+
+        // Each position appears once and its outputs share 1
+        assert_eq!(4520, examples.len());
+        assert!(examples.iter().all(|example| {
+            (example.outputs.iter().fold(0f32, |sum, o| sum + o) - 1f32).abs() < 1e-5
+        }));
+    }
+}
@@ -1,16 +1,28 @@
+pub use self::heuristic::HeuristicPlayer;
 pub use self::human::HumanPlayer;
-pub use self::ai::AiPlayer;
+pub use self::ai::{AiConfig, AiEnvironment, AiPlayer};
+pub use self::coverage::Coverage;
+pub use self::mcts::MctsPlayer;
+pub use self::minimax::MinimaxPlayer;
+pub use self::opponent::Opponent;
+pub use self::random::RandomPlayer;
 
 mod ai;
+mod coverage;
+mod heuristic;
 mod human;
+mod mcts;
+mod minimax;
+mod opponent;
+mod random;
 mod board;
 
 use self::board::Mark;
-use rand;
+use rand::Rng;
 
 pub trait Player {
     fn set_mark(&mut self, board::Mark);
-    fn play(&mut self, &mut board::View);
+    fn play<R: Rng>(&mut self, &mut board::View, &mut R);
     fn game_result(&mut self, GameResult);
 }
 
@@ -20,47 +32,64 @@ pub enum GameResult {
     Tie,
 }
 
-pub fn play_game<P1: Player, P2: Player>(player1: &mut P1, player2: &mut P2) {
+impl GameResult {
+    /// Returns the points the result is worth, with a tie worth half a win
+    pub fn score(&self) -> f64 {
+        match *self {
+            GameResult::Win => 1f64,
+            GameResult::Loss => 0f64,
+            GameResult::Tie => 0.5,
+        }
+    }
+}
+
+/// Plays a game with player1 going first and returns its result for player1
+pub fn play_game<P1: Player, P2: Player, R: Rng>(player1: &mut P1,
+                                                 player2: &mut P2,
+                                                 rng: &mut R)
+                                                 -> GameResult {
     let mut board = board::Board::new();
 
     player1.set_mark(Mark::X);
     player2.set_mark(Mark::O);
 
-    let p2_rotation = rand::random();
+    let p2_rotation = rng.gen();
 
     for i in 0..9 {
         let win;
 
         if (i % 2) == 0 {
             let mut view = board.get_view(board::Rotation::Bottom);
-            win = take_turn(player1, player2, &mut view, i);
+            win = take_turn(player1, player2, &mut view, i, rng);
         } else {
             let mut view = board.get_view(p2_rotation);
-            win = take_turn(player2, player1, &mut view, i);
+            win = take_turn(player2, player1, &mut view, i, rng);
         }
 
         if win {
-            return;
+            return if (i % 2) == 0 { GameResult::Win } else { GameResult::Loss };
         }
 
     }
     // Tie game
     player1.game_result(GameResult::Tie);
     player2.game_result(GameResult::Tie);
+    GameResult::Tie
 }
 
 
-fn take_turn<P: Player, O: Player>(player: &mut P,
-                                   opponent: &mut O,
-                                   board: &mut board::View,
-                                   turn: usize)
-                                   -> bool {
+fn take_turn<P: Player, O: Player, R: Rng>(player: &mut P,
+                                           opponent: &mut O,
+                                           board: &mut board::View,
+                                           turn: usize,
+                                           rng: &mut R)
+                                           -> bool {
 
     // Make move
-    player.play(board);
+    player.play(board, rng);
 
     // Once possible, check for win
-    if turn >= 5 && check_for_win(board) {
+    if turn >= 4 && check_for_win(board) {
         player.game_result(GameResult::Win);
         opponent.game_result(GameResult::Loss);
         return true;
@@ -79,3 +108,44 @@ fn check_for_win(board: &board::View) -> bool {
     (board[0] != Mark::None && board[4] == board[0] && board[8] == board[0]) ||
     (board[2] != Mark::None && board[4] == board[2] && board[6] == board[2])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    /// Plays the first empty space from a fixed list
+    struct Scripted {
+        mark: Mark,
+        moves: Vec<usize>,
+    }
+
+    impl Player for Scripted {
+        fn set_mark(&mut self, mark: Mark) {
+            self.mark = mark;
+        }
+
+        fn play<R: Rng>(&mut self, board: &mut board::View, _rng: &mut R) {
+            let space = self.moves.iter().cloned().find(|&i| board[i] == Mark::None).unwrap();
+            board[space] = self.mark;
+        }
+
+        fn game_result(&mut self, _result: GameResult) {}
+    }
+
+    #[test]
+    fn test_fifth_move_win() {
+        // X completes the diagonal on the fifth move. O only takes edges,
+        // which stay edges whichever way its view is rotated.
+        for seed in 0..8 {
+            let mut rng = StdRng::from_seed(&[seed]);
+            let mut x = Scripted { mark: Mark::None, moves: vec![0, 4, 8] };
+            let mut o = Scripted { mark: Mark::None, moves: vec![1, 3, 5, 7] };
+
+            match play_game(&mut x, &mut o, &mut rng) {
+                GameResult::Win => {}
+                _ => panic!("the win on the fifth move was missed"),
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use neural::Network;
+use rand::Rng;
+use super::{board, Player, GameResult};
+use super::{HeuristicPlayer, MctsPlayer, MinimaxPlayer, RandomPlayer};
+use super::mcts::Budget;
+
+/// One of the fixed players, for baselines that can be chosen at run time
+pub enum Opponent {
+    Random(RandomPlayer),
+    Heuristic(HeuristicPlayer),
+    Minimax(MinimaxPlayer),
+    Mcts(MctsPlayer),
+}
+
+impl Opponent {
+
+    /// Parses `random`, `heuristic`, `minimax[:DEPTH]` or
+    /// `mcts[:BUDGET[:EXPLORATION]]`. The random and heuristic players make
+    /// their choices with a generator seeded with `seed`, while minimax
+    /// breaks ties with the game's and plays perfectly unless it's limited
+    /// to searching `DEPTH` moves ahead, such as `minimax:2`. Tree search
+    /// runs 1000 playouts a move unless given a budget, such as `mcts:5000`
+    /// or `mcts:200ms`, and can be given its UCT exploration constant after
+    /// that.
+    pub fn parse(spec: &str, seed: usize) -> Result<Opponent, String> {
+        let mut parts = spec.splitn(2, ':');
+        let name = parts.next().unwrap();
+        let options = parts.next();
+
+        match (name, options) {
+            ("random", None) => Ok(Opponent::Random(RandomPlayer::new(seed))),
+            ("heuristic", None) => Ok(Opponent::Heuristic(HeuristicPlayer::new(seed))),
+            ("minimax", None) => Ok(Opponent::Minimax(MinimaxPlayer::new().randomised(true))),
+            ("minimax", Some(depth)) => {
+                match depth.parse() {
+                    Ok(depth) if depth > 0 => Ok(Opponent::Minimax(MinimaxPlayer::new().depth(depth).randomised(true))),
+                    _ => Err(format!("invalid search depth '{}'", depth)),
+                }
+            }
+            ("mcts", None) => Ok(Opponent::Mcts(MctsPlayer::new(1000))),
+            ("mcts", Some(options)) => {
+                let mut options = options.splitn(2, ':');
+                let budget = options.next().unwrap().parse::<Budget>()?;
+                let mut player = MctsPlayer::new(1000).budget(budget);
+
+                if let Some(exploration) = options.next() {
+                    match exploration.parse::<f64>() {
+                        Ok(c) if c >= 0f64 => player = player.exploration(c),
+                        _ => return Err(format!("invalid exploration constant '{}'", exploration)),
+                    }
+                }
+                Ok(Opponent::Mcts(player))
+            }
+            _ => Err(format!("unknown opponent '{}'", spec)),
+        }
+    }
+
+    /// Has an MCTS opponent pick its playouts' moves with the network
+    pub fn guided(self, network: Network) -> Result<Opponent, String> {
+        match self {
+            Opponent::Mcts(player) => Ok(Opponent::Mcts(player.network(network))),
+            _ => Err("only an mcts opponent can be guided by a network".to_string()),
+        }
+    }
+}
+
+impl Player for Opponent {
+
+    fn set_mark(&mut self, mark: board::Mark) {
+        match *self {
+            Opponent::Random(ref mut player) => player.set_mark(mark),
+            Opponent::Heuristic(ref mut player) => player.set_mark(mark),
+            Opponent::Minimax(ref mut player) => player.set_mark(mark),
+            Opponent::Mcts(ref mut player) => player.set_mark(mark),
+        }
+    }
+
+    fn play<R: Rng>(&mut self, board: &mut board::View, rng: &mut R) {
+        match *self {
+            Opponent::Random(ref mut player) => player.play(board, rng),
+            Opponent::Heuristic(ref mut player) => player.play(board, rng),
+            Opponent::Minimax(ref mut player) => player.play(board, rng),
+            Opponent::Mcts(ref mut player) => player.play(board, rng),
+        }
+    }
+
+    fn game_result(&mut self, result: GameResult) {
+        match *self {
+            Opponent::Random(ref mut player) => player.game_result(result),
+            Opponent::Heuristic(ref mut player) => player.game_result(result),
+            Opponent::Minimax(ref mut player) => player.game_result(result),
+            Opponent::Mcts(ref mut player) => player.game_result(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::play_game;
+    use neural::Activation;
+    use rand::{SeedableRng, StdRng};
+
+    #[test]
+    fn test_parse() {
+        assert!(Opponent::parse("random", 1).is_ok());
+        assert!(Opponent::parse("heuristic", 1).is_ok());
+        assert!(Opponent::parse("minimax", 1).is_ok());
+        assert!(Opponent::parse("mcts", 1).is_ok());
+        assert!(Opponent::parse("minimax:2", 1).is_ok());
+        assert!(Opponent::parse("minimax:0", 1).is_err());
+        assert!(Opponent::parse("mcts:5000", 1).is_ok());
+        assert!(Opponent::parse("mcts:200ms:0.5", 1).is_ok());
+        assert!(Opponent::parse("grandmaster", 1).is_err());
+        assert!(Opponent::parse("random:3", 1).is_err());
+        assert!(Opponent::parse("mcts:many", 1).is_err());
+        assert!(Opponent::parse("mcts:100:-1", 1).is_err());
+    }
+
+    #[test]
+    fn test_guided() {
+        let mut rng = StdRng::from_seed(&[2]);
+        let network = Network::with_activations(&[9, 9, 9], &[Activation::Tanh, Activation::Softmax], &mut rng);
+
+        assert!(Opponent::parse("mcts:10", 1).unwrap().guided(network.clone()).is_ok());
+        assert!(Opponent::parse("heuristic", 1).unwrap().guided(network).is_err());
+    }
+
+    #[test]
+    fn test_baselines_never_beat_minimax() {
+        let mut rng = StdRng::from_seed(&[4]);
+        let mut minimax = Opponent::parse("minimax", 0).unwrap();
+
+        for name in &["random", "heuristic"] {
+            let mut baseline = Opponent::parse(name, 3).unwrap();
+
+            for _ in 0..20 {
+                match play_game(&mut baseline, &mut minimax, &mut rng) {
+                    GameResult::Win => panic!("{} beat minimax", name),
+                    _ => {}
+                }
+                match play_game(&mut minimax, &mut baseline, &mut rng) {
+                    GameResult::Loss => panic!("{} beat minimax", name),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use rand::{Rng, SeedableRng, StdRng};
+use super::{board, Player, GameResult};
+use super::board::{Mark, empty_spaces};
+
+/// Plays uniformly at random among the empty spaces
+pub struct RandomPlayer {
+    mark: Mark,
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+
+    /// Constructs a `RandomPlayer` whose moves are chosen by a generator
+    /// seeded with `seed`, independently of the game's
+    pub fn new(seed: usize) -> RandomPlayer {
+        RandomPlayer {
+            mark: Mark::None,
+            rng: StdRng::from_seed(&[seed]),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+
+    fn set_mark(&mut self, mark: Mark) {
+        self.mark = mark;
+    }
+
+    fn play<R: Rng>(&mut self, board: &mut board::View, _rng: &mut R) {
+        let spaces = empty_spaces(&board.cells());
+
+        board[*self.rng.choose(&spaces).unwrap()] = self.mark;
+    }
+
+    fn game_result(&mut self, _result: GameResult) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::board::{Board, Rotation};
+    use super::super::board::Mark::{X, O};
+
+    #[test]
+    fn test_plays_empty_spaces() {
+        let mut rng = StdRng::from_seed(&[2]);
+
+        for seed in 0..10 {
+            let mut player = RandomPlayer::new(seed);
+            player.set_mark(O);
+
+            let mut board = Board::new();
+            let mut view = board.get_view(Rotation::Bottom);
+            view[0] = X;
+            view[1] = X;
+            player.play(&mut view, &mut rng);
+
+            let cells = view.cells();
+            assert_eq!((X, X), (cells[0], cells[1]));
+            assert_eq!(1, cells.iter().filter(|&&mark| mark == O).count());
+        }
+    }
+
+    #[test]
+    fn test_seeded() {
+        let moves = |seed| {
+            let mut player = RandomPlayer::new(seed);
+            player.set_mark(X);
+
+            let mut board = Board::new();
+            let mut view = board.get_view(Rotation::Bottom);
+            let mut rng = StdRng::from_seed(&[0]);
+
+            for _ in 0..9 {
+                player.play(&mut view, &mut rng);
+            }
+            view.cells()
+        };
+
+        assert_eq!(moves(5), moves(5));
+        assert!(empty_spaces(&moves(5)).is_empty());
+    }
+}
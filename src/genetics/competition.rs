@@ -0,0 +1,419 @@
+use rand::{Rng, StdRng, XorShiftRng};
+use scoped_threadpool::Pool;
+use std::collections::HashSet;
+use super::{Evaluate, Individual};
+
+/// Decides who competes with whom in a generation. Every scheme gives each
+/// individual the same number of matches so that ratings are comparable.
+pub trait Competition<T: Individual> {
+
+    /// Checks that the scheme can give every individual of a population of
+    /// this size the same number of matches
+    fn check(&self, _num_individuals: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Makes the individuals compete, running the matches of each round on
+    /// the pool
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]);
+}
+
+/// Every individual plays every other individual once
+pub struct RoundRobin;
+
+/// Every individual plays this many randomly chosen, distinct opponents
+pub struct RandomOpponents {
+    pub opponents: usize,
+}
+
+/// This many rounds in which individuals with similar ratings so far are
+/// paired, avoiding rematches where possible
+pub struct Swiss {
+    pub rounds: usize,
+}
+
+/// Every individual plays this many matches against a fresh copy of a fixed
+/// benchmark opponent
+pub struct Benchmark<T> {
+    pub matches: usize,
+    benchmark: Box<Fn() -> T + Send + Sync>,
+}
+
+/// Nobody plays anybody; every individual is evaluated against the
+/// environment instead
+pub struct Evaluation<T: Evaluate> {
+    pub environment: T::Environment,
+}
+
+impl<T> Benchmark<T> {
+    /// Constructs a `Benchmark` whose opponents are made by `benchmark`
+    pub fn new<F>(matches: usize, benchmark: F) -> Benchmark<T>
+        where F: Fn() -> T + Send + Sync + 'static
+    {
+        Benchmark {
+            matches: matches,
+            benchmark: Box::new(benchmark),
+        }
+    }
+}
+
+impl<T: Individual> Competition<T> for RoundRobin {
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]) {
+        // The circle method: one position stays fixed while the others
+        // rotate, with an empty position when the number is odd
+        let num_positions = individuals.len() + individuals.len() % 2;
+
+        for round in 0..num_positions.saturating_sub(1) {
+            let player = |position: usize| {
+                if position == 0 {
+                    0
+                } else {
+                    (position - 1 + round) % (num_positions - 1) + 1
+                }
+            };
+
+            let pairs = (0..num_positions / 2)
+                .map(|i| (player(i), player(num_positions - 1 - i)))
+                .filter(|&(a, b)| a < individuals.len() && b < individuals.len())
+                .collect::<Vec<_>>();
+
+            play_round(pool, rng, individuals, &pairs);
+        }
+    }
+}
+
+impl<T: Individual> Competition<T> for RandomOpponents {
+    fn check(&self, num_individuals: usize) -> Result<(), String> {
+        if self.opponents >= num_individuals.max(1) {
+            Err(format!("{} opponents need more than {} individuals", self.opponents, num_individuals))
+        } else if self.opponents % 2 == 1 && num_individuals % 2 == 1 {
+            Err(format!("an odd number of opponents needs an even number of individuals, not {}",
+                num_individuals))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]) {
+        let num_individuals = individuals.len();
+        assert_eq!(Ok(()), Competition::<T>::check(self, num_individuals));
+
+        // Arrange the individuals in a random circle and connect each to its
+        // nearest neighbours, and to the opposite individual for an odd
+        // number of opponents, so everyone has exactly `opponents`
+        let mut circle = (0..num_individuals).collect::<Vec<_>>();
+        rng.shuffle(&mut circle);
+
+        let mut pairs = Vec::with_capacity(num_individuals * self.opponents / 2);
+
+        for distance in 1..(self.opponents / 2 + 1) {
+            for i in 0..num_individuals {
+                pairs.push((circle[i], circle[(i + distance) % num_individuals]));
+            }
+        }
+
+        if self.opponents % 2 == 1 {
+            for i in 0..num_individuals / 2 {
+                pairs.push((circle[i], circle[i + num_individuals / 2]));
+            }
+        }
+
+        for round in schedule(num_individuals, &pairs) {
+            play_round(pool, rng, individuals, &round);
+        }
+    }
+}
+
+impl<T: Individual> Competition<T> for Swiss {
+    fn check(&self, num_individuals: usize) -> Result<(), String> {
+        if num_individuals % 2 == 1 {
+            Err(format!("swiss pairing needs an even number of individuals, not {}", num_individuals))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]) {
+        assert_eq!(Ok(()), Competition::<T>::check(self, individuals.len()));
+
+        let mut played = HashSet::new();
+
+        // The first round has nothing to go on, so start from a random order
+        let mut order = (0..individuals.len()).collect::<Vec<_>>();
+        rng.shuffle(&mut order);
+
+        for round in 0..self.rounds {
+            if round > 0 {
+                let ratings = individuals.iter().map(|i| i.get_rating()).collect::<Vec<_>>();
+                order.sort_by(|&a, &b| ratings[b].cmp(&ratings[a]));
+            }
+
+            let mut unpaired = order.clone();
+            let mut pairs = Vec::with_capacity(unpaired.len() / 2);
+
+            while !unpaired.is_empty() {
+                let a = unpaired.remove(0);
+
+                // The best placed individual not yet played, or a rematch
+                // with the next one if there's no one left
+                let opponent = unpaired.iter()
+                    .position(|&b| !played.contains(&(a.min(b), a.max(b))))
+                    .unwrap_or(0);
+                let b = unpaired.remove(opponent);
+
+                played.insert((a.min(b), a.max(b)));
+                pairs.push((a, b));
+            }
+
+            play_round(pool, rng, individuals, &pairs);
+        }
+    }
+}
+
+impl<T: Individual> Competition<T> for Benchmark<T> {
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]) {
+        for _ in 0..self.matches {
+            let opponents = individuals.iter().map(|_| (self.benchmark)()).collect();
+            play_opponents(pool, rng, individuals, opponents);
+        }
+    }
+}
+
+impl<T: Evaluate> Competition<T> for Evaluation<T> {
+    fn compete(&mut self, pool: &mut Pool, rng: &mut StdRng, individuals: &mut [T]) {
+        let environment = &self.environment;
+        let rngs = individuals.iter()
+            .map(|_| rng.gen::<XorShiftRng>())
+            .collect::<Vec<_>>();
+
+        pool.scoped(|scope| {
+            for (individual, mut individual_rng) in individuals.iter_mut().zip(rngs) {
+                scope.execute(move || {
+                    individual.evaluate(environment, &mut individual_rng);
+                });
+            }
+        });
+    }
+}
+
+/// Parses a competition scheme by name: `round-robin`, `N-random`,
+/// `N-swiss` or `N-benchmark`, where the benchmark opponents are made by
+/// `benchmark`
+pub fn parse<T, F>(name: &str, benchmark: F) -> Result<Box<Competition<T>>, String>
+    where T: Individual + 'static,
+          F: Fn() -> T + Send + Sync + 'static
+{
+    let number = |suffix: &str| {
+        name.trim_end_matches(suffix)
+            .parse::<usize>()
+            .map_err(|_| format!("invalid number in '{}'", name))
+    };
+
+    if name == "round-robin" {
+        Ok(Box::new(RoundRobin))
+    } else if name.ends_with("-random") {
+        Ok(Box::new(RandomOpponents { opponents: number("-random")? }))
+    } else if name.ends_with("-swiss") {
+        Ok(Box::new(Swiss { rounds: number("-swiss")? }))
+    } else if name.ends_with("-benchmark") {
+        Ok(Box::new(Benchmark::new(number("-benchmark")?, benchmark)))
+    } else {
+        Err(format!("unknown competition '{}'", name))
+    }
+}
+
+/// Splits the pairs into rounds in which nobody plays more than once
+fn schedule(num_individuals: usize, pairs: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    let mut rounds: Vec<(Vec<(usize, usize)>, Vec<bool>)> = Vec::new();
+
+    for &(a, b) in pairs {
+        let free = rounds.iter().position(|&(_, ref busy)| !busy[a] && !busy[b]);
+        let index = match free {
+            Some(index) => index,
+            None => {
+                rounds.push((Vec::new(), vec![false; num_individuals]));
+                rounds.len() - 1
+            }
+        };
+
+        let (ref mut round, ref mut busy) = rounds[index];
+        round.push((a, b));
+        busy[a] = true;
+        busy[b] = true;
+    }
+
+    rounds.into_iter().map(|(round, _)| round).collect()
+}
+
+/// Plays a round of pairs, in which nobody appears twice, on the pool. Every
+/// pair gets its own stream, drawn in order, so the results don't depend on
+/// how the pairs are spread across threads.
+fn play_round<T: Individual>(pool: &mut Pool,
+                             rng: &mut StdRng,
+                             individuals: &mut [T],
+                             pairs: &[(usize, usize)]) {
+    let rngs = pairs.iter()
+        .map(|_| rng.gen::<XorShiftRng>())
+        .collect::<Vec<_>>();
+
+    let mut slots = individuals.iter_mut().map(Some).collect::<Vec<_>>();
+
+    pool.scoped(|scope| {
+        for (&(a, b), mut pair_rng) in pairs.iter().zip(rngs) {
+            let individual1 = slots[a].take().expect("individual paired twice in a round");
+            let individual2 = slots[b].take().expect("individual paired twice in a round");
+
+            scope.execute(move || {
+                individual1.compete(individual2, &mut pair_rng);
+            });
+        }
+    });
+}
+
+/// Plays each individual against its own outside opponent, whose results are
+/// thrown away, on the pool
+pub(crate) fn play_opponents<T: Individual>(pool: &mut Pool,
+                                            rng: &mut StdRng,
+                                            individuals: &mut [T],
+                                            opponents: Vec<T>) {
+    let rngs = individuals.iter()
+        .map(|_| rng.gen::<XorShiftRng>())
+        .collect::<Vec<_>>();
+
+    pool.scoped(|scope| {
+        for ((individual, mut opponent), mut match_rng) in individuals.iter_mut().zip(opponents).zip(rngs) {
+            scope.execute(move || {
+                individual.compete(&mut opponent, &mut match_rng);
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashSet;
+
+    /// Keeps track of who it has played
+    #[derive(Clone, Debug)]
+    struct Counter {
+        id: usize,
+        opponents: Vec<usize>,
+    }
+
+    impl Individual for Counter {
+        type Config = ();
+
+        fn new<R: Rng>(_config: &(), _rng: &mut R) -> Counter {
+            Counter { id: usize::max_value(), opponents: Vec::new() }
+        }
+
+        fn reproduce<R: Rng>(&self, _mate: &Counter, config: &(), rng: &mut R) -> Counter {
+            Counter::new(config, rng)
+        }
+
+        fn get_rating(&self) -> i32 {
+            // Lower ids win, so the ratings separate as the rounds go on
+            self.opponents.iter().filter(|&&opponent| opponent > self.id).count() as i32
+        }
+
+        fn reset(&mut self) {
+            self.opponents.clear();
+        }
+
+        fn compete<R: Rng>(&mut self, other: &mut Counter, _rng: &mut R) {
+            self.opponents.push(other.id);
+            other.opponents.push(self.id);
+        }
+    }
+
+    impl Evaluate for Counter {
+        /// The id recorded as the opponent
+        type Environment = usize;
+
+        fn evaluate<R: Rng>(&mut self, environment: &usize, _rng: &mut R) {
+            self.opponents.push(*environment);
+        }
+    }
+
+    fn counters(num: usize) -> Vec<Counter> {
+        (0..num).map(|id| Counter { id: id, opponents: Vec::new() }).collect()
+    }
+
+    fn run<C: Competition<Counter>>(mut competition: C, num: usize) -> Vec<Counter> {
+        let mut individuals = counters(num);
+        competition.compete(&mut Pool::new(3), &mut StdRng::from_seed(&[5]), &mut individuals);
+        individuals
+    }
+
+    /// Asserts everyone played `matches` matches and, if `distinct`, never
+    /// the same opponent twice
+    fn assert_matches(individuals: &[Counter], matches: usize, distinct: bool) {
+        for individual in individuals {
+            assert_eq!(matches, individual.opponents.len());
+            assert!(!individual.opponents.contains(&individual.id));
+
+            if distinct {
+                let unique = individual.opponents.iter().collect::<HashSet<_>>();
+                assert_eq!(matches, unique.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        assert_matches(&run(RoundRobin, 6), 5, true);
+        assert_matches(&run(RoundRobin, 7), 6, true);
+        assert_matches(&run(RoundRobin, 1), 0, true);
+    }
+
+    #[test]
+    fn test_random_opponents() {
+        assert_matches(&run(RandomOpponents { opponents: 3 }, 10), 3, true);
+        assert_matches(&run(RandomOpponents { opponents: 4 }, 9), 4, true);
+        assert_matches(&run(RandomOpponents { opponents: 8 }, 9), 8, true);
+
+        assert!(Competition::<Counter>::check(&RandomOpponents { opponents: 3 }, 9).is_err());
+        assert!(Competition::<Counter>::check(&RandomOpponents { opponents: 9 }, 9).is_err());
+    }
+
+    #[test]
+    fn test_swiss() {
+        let individuals = run(Swiss { rounds: 4 }, 8);
+        assert_matches(&individuals, 4, true);
+
+        // The unbeaten first individual next meets someone who won their first match
+        let opponent = individuals[0].opponents[1];
+        assert!(individuals[opponent].opponents[0] > opponent);
+
+        assert!(Competition::<Counter>::check(&Swiss { rounds: 4 }, 7).is_err());
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let individuals = run(Benchmark::new(3, || Counter { id: 100, opponents: Vec::new() }), 5);
+
+        assert_matches(&individuals, 3, false);
+        assert!(individuals.iter().all(|individual| individual.opponents.iter().all(|&o| o == 100)));
+    }
+
+    #[test]
+    fn test_evaluation() {
+        let individuals = run(Evaluation { environment: 42 }, 5);
+
+        assert!(individuals.iter().all(|individual| individual.opponents == vec![42]));
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |name| parse(name, || Counter::new(&(), &mut StdRng::from_seed(&[0]))).is_ok();
+
+        assert!(parse("round-robin"));
+        assert!(parse("4-random"));
+        assert!(parse("5-swiss"));
+        assert!(parse("10-benchmark"));
+        assert!(!parse("x-swiss"));
+        assert!(!parse("knockout"));
+    }
+}
@@ -1,123 +1,769 @@
 use scoped_threadpool::Pool;
-use super::Individual;
-use rand;
-use rand::Rng;
+use super::{Competition, GenerationStats, HallOfFame, Individual, Observer, RoundRobin, Selection,
+            StatsLog, StopReason, Termination, Truncation};
+use super::competition::play_opponents;
+use super::speciation;
+use rand::{self, Rng, SeedableRng, StdRng};
 use num_cpus;
+use std::error;
+use std::fmt;
+use std::time::Instant;
+
+/// The number of generations whose statistics are kept in memory
+const HISTORY_LENGTH: usize = 100;
 
 pub struct Evolution<T: Individual> {
     generation: u32,
     generation_size: usize,
-    survival_rate: f32,
+    num_survivors: usize,
+    num_elites: usize,
+    num_immigrants: usize,
+    config: T::Config,
+    selection: Box<Selection>,
+    competition: Box<Competition<T>>,
+    hall_of_fame: HallOfFame<T>,
+    speciation: Option<f64>,
+    species: Vec<Vec<usize>>,
+    history: Vec<GenerationStats>,
+    observers: Vec<Box<Observer<T>>>,
     pub individuals: Vec<T>,
     thread_pool: Pool,
+    rng: StdRng,
 }
 
-impl<T: Individual> Evolution<T> {
+/// Configures and validates an `Evolution` before the first generation is created
+pub struct EvolutionBuilder<T: Individual> {
+    generation_size: usize,
+    config: T::Config,
+    seed: Option<usize>,
+    selection: Box<Selection>,
+    competition: Box<Competition<T>>,
+    hall_of_fame: (usize, usize),
+    speciation: Option<f64>,
+    survival_rate: f32,
+    elites: Option<usize>,
+    immigrant_rate: f32,
+    observers: Vec<Box<Observer<T>>>,
+}
 
-    /// Constructs a new `Evolution` with the given number of individuals per generation
-    /// and populates the first generation with new individuals
-    pub fn new(generation_size: usize) -> Evolution<T> {
+/// The reason an `EvolutionBuilder` couldn't build an `Evolution`
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// The generation has no individuals
+    EmptyGeneration,
+    /// The survival rate isn't within (0, 1]
+    SurvivalRate(f32),
+    /// The survival rate leaves no individuals to breed from
+    NoSurvivors { generation_size: usize, survival_rate: f32 },
+    /// There are more elites than survivors
+    Elites { elites: usize, survivors: usize },
+    /// The immigrant rate isn't within [0, 1)
+    ImmigrantRate(f32),
+    /// The elites and immigrants leave no room for children
+    Overfull { elites: usize, immigrants: usize, generation_size: usize },
+    /// The competition can't be held with this many individuals
+    Competition(String),
+    /// The speciation threshold is negative
+    SpeciationThreshold(f64),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::EmptyGeneration => write!(f, "a generation needs at least one individual"),
+            BuildError::SurvivalRate(rate) => write!(f, "survival rate {} is not within (0, 1]", rate),
+            BuildError::NoSurvivors { generation_size, survival_rate } => {
+                write!(f, "a survival rate of {} leaves none of {} individuals to breed from",
+                    survival_rate, generation_size)
+            }
+            BuildError::Elites { elites, survivors } => {
+                write!(f, "{} elites is more than the {} survivors", elites, survivors)
+            }
+            BuildError::ImmigrantRate(rate) => write!(f, "immigrant rate {} is not within [0, 1)", rate),
+            BuildError::Overfull { elites, immigrants, generation_size } => {
+                write!(f, "{} elites and {} immigrants leave no room for children in a generation of {}",
+                    elites, immigrants, generation_size)
+            }
+            BuildError::Competition(ref message) => write!(f, "{}", message),
+            BuildError::SpeciationThreshold(threshold) => {
+                write!(f, "speciation threshold {} is negative", threshold)
+            }
+        }
+    }
+}
+
+impl error::Error for BuildError {
+    fn description(&self) -> &str {
+        "invalid evolution settings"
+    }
+}
+
+impl<T: Individual> EvolutionBuilder<T> {
+
+    /// Uses the seed for every random decision so that the evolution can be
+    /// repeated. A random seed is picked otherwise.
+    pub fn seed(mut self, seed: usize) -> EvolutionBuilder<T> {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets how parents are chosen from the survivors
+    pub fn selection(mut self, selection: Box<Selection>) -> EvolutionBuilder<T> {
+        self.selection = selection;
+        self
+    }
+
+    /// Sets who competes with whom in each generation
+    pub fn competition(mut self, competition: Box<Competition<T>>) -> EvolutionBuilder<T> {
+        self.competition = competition;
+        self
+    }
+
+    /// Keeps the best individual of each of the last `capacity` generations
+    /// and has every individual also play a sample of `opponents` of them
+    pub fn hall_of_fame(mut self, capacity: usize, opponents: usize) -> EvolutionBuilder<T> {
+        self.hall_of_fame = (capacity, opponents);
+        self
+    }
+
+    /// Groups individuals within `threshold` of each other into species, which
+    /// share their fitness and breed among themselves
+    pub fn speciation(mut self, threshold: f64) -> EvolutionBuilder<T> {
+        self.speciation = Some(threshold);
+        self
+    }
+
+    /// Sets the fraction of each generation, best first, that is allowed to
+    /// breed
+    pub fn survival_rate(mut self, survival_rate: f32) -> EvolutionBuilder<T> {
+        self.survival_rate = survival_rate;
+        self
+    }
+
+    /// Sets the number of the best individuals copied unchanged into the next
+    /// generation. Every survivor is kept by default, unless that would leave
+    /// no room for children.
+    pub fn elites(mut self, elites: usize) -> EvolutionBuilder<T> {
+        self.elites = Some(elites);
+        self
+    }
+
+    /// Sets the fraction of each generation replaced by brand new individuals
+    pub fn immigrant_rate(mut self, immigrant_rate: f32) -> EvolutionBuilder<T> {
+        self.immigrant_rate = immigrant_rate;
+        self
+    }
+
+    /// Writes the statistics of every generation to the log
+    pub fn log(self, log: StatsLog) -> EvolutionBuilder<T> {
+        self.observer(Box::new(log))
+    }
+
+    /// Adds an observer that is called as the evolution runs
+    pub fn observer(mut self, observer: Box<Observer<T>>) -> EvolutionBuilder<T> {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Validates the settings and populates the first generation
+    pub fn build(self) -> Result<Evolution<T>, BuildError> {
+        let generation_size = self.generation_size;
+
+        if generation_size == 0 {
+            return Err(BuildError::EmptyGeneration);
+        }
+
+        if !(self.survival_rate > 0f32 && self.survival_rate <= 1f32) {
+            return Err(BuildError::SurvivalRate(self.survival_rate));
+        }
+
+        let num_survivors = (generation_size as f32 * self.survival_rate) as usize;
+
+        if num_survivors == 0 {
+            return Err(BuildError::NoSurvivors {
+                generation_size: generation_size,
+                survival_rate: self.survival_rate,
+            });
+        }
+
+        if !(self.immigrant_rate >= 0f32 && self.immigrant_rate < 1f32) {
+            return Err(BuildError::ImmigrantRate(self.immigrant_rate));
+        }
+
+        let num_immigrants = (generation_size as f32 * self.immigrant_rate) as usize;
+
+        // By default keep every survivor that fits alongside at least one child
+        let num_elites = self.elites.unwrap_or_else(|| {
+            num_survivors.min(generation_size.saturating_sub(num_immigrants + 1))
+        });
+
+        if num_elites > num_survivors {
+            return Err(BuildError::Elites { elites: num_elites, survivors: num_survivors });
+        }
+
+        // A generation made only of elites can never change
+        if num_elites + num_immigrants >= generation_size {
+            return Err(BuildError::Overfull {
+                elites: num_elites,
+                immigrants: num_immigrants,
+                generation_size: generation_size,
+            });
+        }
+
+        if let Some(threshold) = self.speciation {
+            if !(threshold >= 0f64) {
+                return Err(BuildError::SpeciationThreshold(threshold));
+            }
+        }
+
+        self.competition.check(generation_size).map_err(BuildError::Competition)?;
+
+        let seed = self.seed.unwrap_or_else(|| rand::random::<u32>() as usize);
+        let mut rng = StdRng::from_seed(&[seed]);
         let mut individuals = Vec::with_capacity(generation_size);
 
         for _ in 0..generation_size {
-            individuals.push(T::new());
+            individuals.push(T::new(&self.config, &mut rng));
         }
 
-        Evolution {
+        Ok(Evolution {
             generation: 0,
             generation_size: generation_size,
-            survival_rate: 0.5,
+            num_survivors: num_survivors,
+            num_elites: num_elites,
+            num_immigrants: num_immigrants,
+            config: self.config,
+            selection: self.selection,
+            competition: self.competition,
+            hall_of_fame: HallOfFame::new(self.hall_of_fame.0, self.hall_of_fame.1),
+            speciation: self.speciation,
+            species: Vec::new(),
+            history: Vec::new(),
+            observers: self.observers,
             individuals: individuals,
-            thread_pool: Pool::new(num_cpus::get() as u32), // TODO replace with num_cpus
+            thread_pool: Pool::new(num_cpus::get() as u32),
+            rng: rng,
+        })
+    }
+}
+
+impl<T: Individual> Evolution<T> {
+
+    /// Starts building an `Evolution` with the given number of individuals per
+    /// generation. By default everyone plays everyone, the best half survive
+    /// and every survivor breeds and is kept, as long as that leaves room for
+    /// a child.
+    pub fn builder(generation_size: usize, config: T::Config) -> EvolutionBuilder<T> {
+        EvolutionBuilder {
+            generation_size: generation_size,
+            config: config,
+            seed: None,
+            // Parents are already limited to the survivors
+            selection: Box::new(Truncation { fraction: 1f32 }),
+            competition: Box::new(RoundRobin),
+            hall_of_fame: (0, 0),
+            speciation: None,
+            survival_rate: 0.5,
+            elites: None,
+            immigrant_rate: 0f32,
+            observers: Vec::new(),
         }
     }
     
     /// Evolves for the given number of generations
     pub fn evolve(&mut self, generations: u32) {
+        if generations > 0 {
+            self.evolve_until(Termination::new().generations(generations));
+        }
+    }
+
+    /// Evolves until one of the termination conditions is met and returns
+    /// the reason it stopped. The generation that meets a condition isn't
+    /// bred, so the population is left ranked best first.
+    pub fn evolve_until(&mut self, mut termination: Termination<T>) -> StopReason {
+        let started = Instant::now();
+
+        // A population left ranked by an earlier run, or saved by one,
+        // competes again from fresh counters
+        for individual in self.individuals.iter_mut() {
+            individual.reset();
+        }
         
-        for _ in 0..generations {
+        loop {
+            let generation_started = Instant::now();
+            
             self.repopulate();
+            
+            for observer in self.observers.iter_mut() {
+                observer.on_generation_start(self.generation, &self.individuals);
+            }
 
             // Make the individuals compete
-            Evolution::bisect_individuals(&mut self.thread_pool, &mut self.individuals);   
+            self.competition.compete(&mut self.thread_pool, &mut self.rng, &mut self.individuals);
+            self.play_hall_of_fame();
 
-			self.find_fittest();
+			let stats = self.find_fittest();
+			let reason = termination.check(&stats, &self.individuals[0], started);
 			
-            self.generation += 1;
+			if self.history.len() == HISTORY_LENGTH {
+				self.history.remove(0);
+			}
+			self.history.push(stats);
+			
+			let mut champion = self.individuals[0].clone();
+			champion.reset();
+			self.hall_of_fame.induct(champion);
+			
+			// Observers see the ranked population, which is also what's left
+			// once the evolution stops
+			let elapsed = generation_started.elapsed();
+			self.finish_generation(elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9);
+			
+            if let Some(reason) = reason {
+                self.generation += 1;
+                return reason;
+            }
             
-            println!("Generation: {}", self.generation);
+            self.next_generation();
+            self.generation += 1;
         }
+    }
 
+    /// Returns the champions of past generations, oldest first
+    pub fn hall_of_fame(&self) -> &[T] {
+        self.hall_of_fame.members()
     }
 
-    fn bisect_individuals(pool: &mut Pool, individuals: &mut [T]) {
-        let length = individuals.len();
-        let bisect = length - (length / 2);
-        
-        let (group1, group2) = individuals.split_at_mut(bisect);
-        
-        Evolution::compete_groups(pool, group1, group2);
-        
-        if bisect > 1 {
-            Evolution::bisect_individuals(pool, group1);
-            Evolution::bisect_individuals(pool, group2);
-        }
+    /// Replaces the hall of fame, e.g. with one loaded from a saved state
+    pub fn set_hall_of_fame(&mut self, members: Vec<T>) {
+        self.hall_of_fame.set_members(members);
     }
 
-    fn compete_groups(pool: &mut Pool, group1: &mut [T], group2: &mut [T]) {
+    /// Has every individual play each of a sample of the hall of fame, so that
+    /// strategies that beat earlier champions aren't forgotten
+    fn play_hall_of_fame(&mut self) {
+        let sample = self.hall_of_fame.sample(&mut self.rng)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
         
-        // This assumes group1 is larger if they aren't the same size
-        assert!(group2.len() <= group1.len());
-        for i in 0..group1.len() {
-            let (front, back) = group1.split_at_mut(i);
-            let group1_iter = back.iter_mut()
-                                  .chain(front.iter_mut());
+        for member in sample {
+            let opponents = self.individuals.iter().map(|_| member.clone()).collect();
+            play_opponents(&mut self.thread_pool, &mut self.rng, &mut self.individuals, opponents);
+        }
+    }
 
-            let pairs = group1_iter.zip(group2.iter_mut());
+    /// Adds an observer that is called as the evolution runs
+    pub fn add_observer(&mut self, observer: Box<Observer<T>>) {
+        self.observers.push(observer);
+    }
 
-            pool.scoped(|scope| {
-                for (individual1, individual2) in pairs {
-                    scope.execute(move || {
-                            individual1.compete(individual2);
-                    });
-                }
-            });
+    /// Returns the statistics of the most recent generations, up to
+    /// `HISTORY_LENGTH` of them. A `StatsLog` keeps every generation's.
+    pub fn history(&self) -> &[GenerationStats] {
+        &self.history
+    }
 
+    fn finish_generation(&mut self, seconds: f64) {
+        let stats = self.history.last_mut().unwrap();
+        stats.seconds = seconds;
+        
+        for observer in self.observers.iter_mut() {
+            observer.on_generation_end(stats, &self.individuals, self.hall_of_fame.members());
         }
     }
 
-    fn find_fittest(&mut self) {
+    /// Sorts the individuals best first and returns the statistics of the
+    /// generation that just competed
+    fn find_fittest(&mut self) -> GenerationStats {
+        for individual in self.individuals.iter_mut() {
+            individual.conclude();
+        }
+
         // Sort by wins/loses/mistakes
         self.individuals.sort_by(|a, b| {
             b.get_rating().cmp(&a.get_rating())
         });
-
-        // Keep only the best
-        self.individuals.truncate((self.generation_size as f32 * self.survival_rate) as usize);
         
-        {
-        	let best = &self.individuals[0];
-        	println!("Generation: {}, {:?}", self.generation, best);
+        self.species = match self.speciation {
+            Some(threshold) => speciation::speciate(&self.individuals, threshold),
+            None => vec![(0..self.individuals.len()).collect()],
+        };
+        
+        let mut stats = {
+            let ratings = self.individuals.iter().map(|i| i.get_rating()).collect::<Vec<_>>();
+            let records = self.individuals.iter().map(|i| i.record()).collect::<Vec<_>>();
+            
+            GenerationStats::new(self.generation, &ratings, &records)
+        };
+        stats.species = self.species.len();
+        
+        for observer in self.observers.iter_mut() {
+            observer.on_competition_complete(self.generation, &self.individuals);
         }
         
+        stats
+    }
+
+    /// Replaces the sorted individuals with the next generation
+    fn next_generation(&mut self) {
+        // Breed the next generation while the survivors' ratings are known
+        let num_children = self.generation_size - self.num_elites - self.num_immigrants;
+        let children = self.breed(num_children);
+        
+        // Keep only the elites
+        self.individuals.truncate(self.num_elites);
+        
         // Reset the ratings
         for individual in self.individuals.iter_mut() {
             individual.reset();
         }
+        
+        self.individuals.extend(children);
+        
+        for _ in 0..self.num_immigrants {
+            let immigrant = T::new(&self.config, &mut self.rng);
+            self.individuals.push(immigrant);
+        }
+    }
+
+    /// Produces children from survivors chosen by the selection strategy
+    fn breed(&mut self, num_children: usize) -> Vec<T> {
+        let ratings = self.individuals.iter()
+            .map(|individual| individual.get_rating())
+            .collect::<Vec<_>>();
+        let allocation = speciation::allocate(&self.species, &ratings, num_children);
+        
+        // Each species breeds its share of the children from its own best
+        // members, in proportion to the population's survivors
+        let mut parents = Vec::with_capacity(num_children * 2);
+        
+        for (members, &count) in self.species.iter().zip(&allocation) {
+            let num_survivors = (members.len() * self.num_survivors / self.generation_size).max(1);
+            let members = &members[..num_survivors.min(members.len())];
+            let member_ratings = members.iter().map(|&i| ratings[i]).collect::<Vec<_>>();
+            
+            let chosen = self.selection.select(&member_ratings, count * 2, &mut self.rng);
+            parents.extend(chosen.into_iter().map(|i| members[i]));
+        }
+        
+        for observer in self.observers.iter_mut() {
+            observer.on_selection(self.generation, &parents, &self.individuals);
+        }
+        
+        parents.chunks(2)
+            .map(|pair| {
+                self.individuals[pair[0]].reproduce(&self.individuals[pair[1]], &self.config, &mut self.rng)
+            })
+            .collect()
     }
 
     fn repopulate(&mut self) {
-        let mut rng = rand::thread_rng();
+        // Drop any extras, e.g. from a larger saved state, so the competition
+        // gets the population size it was checked against
+        self.individuals.truncate(self.generation_size);
+        
         let num_survivors = self.individuals.len();
 
+        // Nobody is left to breed from, e.g. an empty saved state
+        if num_survivors == 0 {
+            for _ in 0..self.generation_size {
+                let individual = T::new(&self.config, &mut self.rng);
+                self.individuals.push(individual);
+            }
+            return;
+        }
+
         // Repopulate any culled players
-        for i in 0..(self.generation_size - num_survivors) {
+        for i in 0..self.generation_size.saturating_sub(num_survivors) {
             
+            let mate = self.rng.gen_range(0, num_survivors);
             let child = self.individuals[i % num_survivors]
-                        .reproduce(&self.individuals[rng.gen_range(0, num_survivors)]);
+                        .reproduce(&self.individuals[mate], &self.config, &mut self.rng);
 
             self.individuals.push(child);
         }
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genetics::competition::Swiss;
+    use genetics::selection::Tournament;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// An individual that competes by guessing random numbers
+    #[derive(Clone, Debug, PartialEq)]
+    struct Guesser {
+        genes: Vec<u32>,
+        score: i32,
+    }
+
+    impl Guesser {
+        fn distance(&self, target: u32) -> u32 {
+            self.genes.iter()
+                .map(|&gene| if gene > target { gene - target } else { target - gene })
+                .min()
+                .unwrap()
+        }
+    }
+
+    impl Individual for Guesser {
+        type Config = ();
+
+        fn new<R: Rng>(_config: &(), rng: &mut R) -> Guesser {
+            Guesser {
+                genes: (0..4).map(|_| rng.gen_range(0, 100)).collect(),
+                score: 0,
+            }
+        }
+
+        fn reproduce<R: Rng>(&self, mate: &Guesser, _config: &(), rng: &mut R) -> Guesser {
+            Guesser {
+                genes: self.genes.iter()
+                    .zip(&mate.genes)
+                    .map(|(&a, &b)| if rng.gen_weighted_bool(10) { rng.gen_range(0, 100) } else if rng.gen() { a } else { b })
+                    .collect(),
+                score: 0,
+            }
+        }
+
+        fn get_rating(&self) -> i32 {
+            self.score
+        }
+
+        fn reset(&mut self) {
+            self.score = 0;
+        }
+
+        fn distance(&self, other: &Guesser) -> f64 {
+            self.genes.iter()
+                .zip(&other.genes)
+                .fold(0f64, |sum, (&a, &b)| sum + (a as f64 - b as f64).abs())
+        }
+
+        fn compete<R: Rng>(&mut self, other: &mut Guesser, rng: &mut R) {
+            let target = rng.gen_range(0, 100);
+
+            if self.distance(target) <= other.distance(target) {
+                self.score += 1;
+            } else {
+                other.score += 1;
+            }
+        }
+    }
+
+    fn run(seed: usize, threads: u32) -> Vec<Guesser> {
+        let mut evolution = Evolution::<Guesser>::builder(20, ())
+            .seed(seed)
+            .selection(Box::new(Tournament { size: 3 }))
+            .elites(2)
+            .immigrant_rate(0.1)
+            .build()
+            .unwrap();
+        evolution.thread_pool = Pool::new(threads);
+
+        evolution.evolve(3);
+        evolution.individuals
+    }
+
+    #[test]
+    fn test_seed_reproducible() {
+        assert_eq!(run(42, 1), run(42, 4));
+        assert!(run(42, 2) != run(43, 2));
+    }
+
+    #[test]
+    fn test_generation_sizes() {
+        let evolution = Evolution::<Guesser>::builder(10, ())
+            .survival_rate(0.4)
+            .elites(1)
+            .immigrant_rate(0.2)
+            .build()
+            .unwrap();
+        assert_eq!((4, 1, 2), (evolution.num_survivors, evolution.num_elites, evolution.num_immigrants));
+
+        // Every survivor is kept by default
+        let evolution = Evolution::<Guesser>::builder(10, ()).build().unwrap();
+        assert_eq!(5, evolution.num_elites);
+
+        // Unless that would leave no room for children
+        let evolution = Evolution::<Guesser>::builder(10, ())
+            .survival_rate(1f32)
+            .immigrant_rate(0.2)
+            .build()
+            .unwrap();
+        assert_eq!((10, 7, 2), (evolution.num_survivors, evolution.num_elites, evolution.num_immigrants));
+
+        // A single individual breeds with itself
+        let mut evolution = Evolution::<Guesser>::builder(1, ())
+            .survival_rate(1f32)
+            .elites(0)
+            .build()
+            .unwrap();
+        evolution.evolve(2);
+        assert_eq!(1, evolution.individuals.len());
+        assert_eq!(vec![0, 1], evolution.history().iter().map(|s| s.generation).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_speciation() {
+        let mut evolution = Evolution::<Guesser>::builder(20, ())
+            .seed(6)
+            .speciation(10f64)
+            .build()
+            .unwrap();
+
+        evolution.evolve(3);
+
+        assert_eq!(20, evolution.individuals.len());
+        assert!(evolution.history().iter().all(|stats| stats.species > 1));
+
+        // With no threshold the whole population is one species
+        let mut evolution = Evolution::<Guesser>::builder(20, ()).seed(6).build().unwrap();
+        evolution.evolve(1);
+        assert_eq!(1, evolution.history()[0].species);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let build = |size, survival_rate, elites, immigrant_rate| {
+            Evolution::<Guesser>::builder(size, ())
+                .survival_rate(survival_rate)
+                .elites(elites)
+                .immigrant_rate(immigrant_rate)
+                .build()
+                .err()
+        };
+
+        assert_eq!(Some(BuildError::EmptyGeneration), build(0, 0.5, 0, 0f32));
+        assert_eq!(Some(BuildError::SurvivalRate(0f32)), build(10, 0f32, 0, 0f32));
+        assert_eq!(Some(BuildError::SurvivalRate(1.5)), build(10, 1.5, 0, 0f32));
+        assert_eq!(Some(BuildError::NoSurvivors { generation_size: 3, survival_rate: 0.2 }),
+                   build(3, 0.2, 0, 0f32));
+        assert_eq!(Some(BuildError::Elites { elites: 6, survivors: 5 }), build(10, 0.5, 6, 0f32));
+        assert_eq!(Some(BuildError::ImmigrantRate(1f32)), build(10, 0.5, 0, 1f32));
+        assert_eq!(Some(BuildError::Overfull { elites: 5, immigrants: 5, generation_size: 10 }),
+                   build(10, 0.5, 5, 0.5));
+
+        let swiss = Evolution::<Guesser>::builder(9, ()).competition(Box::new(Swiss { rounds: 3 })).build();
+        assert!(match swiss {
+            Err(BuildError::Competition(_)) => true,
+            _ => false,
+        });
+    }
+
+    /// Records the order of the hooks and the population sizes they see
+    struct Recorder(Rc<RefCell<Vec<(&'static str, u32, usize)>>>);
+
+    impl Observer<Guesser> for Recorder {
+        fn on_generation_start(&mut self, generation: u32, individuals: &[Guesser]) {
+            self.0.borrow_mut().push(("start", generation, individuals.len()));
+        }
+
+        fn on_competition_complete(&mut self, generation: u32, individuals: &[Guesser]) {
+            assert!(individuals.windows(2).all(|pair| pair[0].score >= pair[1].score));
+            self.0.borrow_mut().push(("competed", generation, individuals.len()));
+        }
+
+        fn on_selection(&mut self, generation: u32, parents: &[usize], _individuals: &[Guesser]) {
+            self.0.borrow_mut().push(("selected", generation, parents.len()));
+        }
+
+        fn on_generation_end(&mut self, stats: &GenerationStats, individuals: &[Guesser], _hall_of_fame: &[Guesser]) {
+            assert!(individuals.windows(2).all(|pair| pair[0].score >= pair[1].score));
+            self.0.borrow_mut().push(("end", stats.generation, individuals.len()));
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut evolution = Evolution::<Guesser>::builder(10, ())
+            .seed(1)
+            .elites(2)
+            .observer(Box::new(Recorder(calls.clone())))
+            .build()
+            .unwrap();
+
+        evolution.evolve(2);
+
+        assert_eq!(vec![("start", 0, 10), ("competed", 0, 10), ("end", 0, 10), ("selected", 0, 16),
+                        ("start", 1, 10), ("competed", 1, 10), ("end", 1, 10)],
+                   *calls.borrow());
+    }
+
+    /// Records every parent chosen
+    struct Parents(Rc<RefCell<Vec<usize>>>);
+
+    impl Observer<Guesser> for Parents {
+        fn on_selection(&mut self, _generation: u32, parents: &[usize], _individuals: &[Guesser]) {
+            self.0.borrow_mut().extend_from_slice(parents);
+        }
+    }
+
+    #[test]
+    fn test_every_survivor_breeds() {
+        let parents = Rc::new(RefCell::new(Vec::new()));
+        let mut evolution = Evolution::<Guesser>::builder(20, ())
+            .seed(2)
+            .elites(0)
+            .observer(Box::new(Parents(parents.clone())))
+            .build()
+            .unwrap();
+
+        evolution.evolve(3);
+
+        // The best half survive and the default selection doesn't cut them
+        // down any further
+        let parents = parents.borrow();
+        assert!(parents.iter().all(|&i| i < 10));
+        assert!(parents.iter().any(|&i| i >= 5));
+    }
+
+    #[test]
+    fn test_history_bounded() {
+        let mut evolution = Evolution::<Guesser>::builder(4, ()).seed(3).build().unwrap();
+
+        evolution.evolve(HISTORY_LENGTH as u32 + 5);
+
+        assert_eq!(HISTORY_LENGTH, evolution.history().len());
+        assert_eq!(5, evolution.history()[0].generation);
+    }
+
+    #[test]
+    fn test_evolve_until() {
+        let mut evolution = Evolution::<Guesser>::builder(10, ()).seed(3).build().unwrap();
+
+        assert_eq!(StopReason::Generations(4), evolution.evolve_until(Termination::new().generations(4)));
+        assert_eq!(4, evolution.history().len());
+
+        // Generations are counted from the start of each call
+        let reason = evolution.evolve_until(Termination::new().generations(2).target_rating(-100));
+        assert_eq!(StopReason::TargetRating(evolution.history()[4].best), reason);
+        assert_eq!(5, evolution.history().len());
+    }
+
+    #[test]
+    fn test_hall_of_fame() {
+        let mut evolution = Evolution::<Guesser>::builder(10, ())
+            .seed(4)
+            .hall_of_fame(3, 2)
+            .build()
+            .unwrap();
+
+        evolution.evolve(1);
+        assert_eq!(1, evolution.hall_of_fame().len());
+        assert_eq!(0, evolution.hall_of_fame()[0].score);
+
+        // The first generation only plays itself, with one point per match,
+        // while later ones also win points from the champions
+        evolution.evolve(3);
+        assert_eq!(3, evolution.hall_of_fame().len());
+        assert_eq!(4.5, evolution.history()[0].mean);
+        assert!(evolution.history()[3].mean > 4.5);
+    }
+}
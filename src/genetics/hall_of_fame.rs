@@ -0,0 +1,93 @@
+use rand::{self, Rng};
+
+/// The champions of past generations, kept so that later generations can be
+/// tested against the strategies that beat earlier ones
+pub struct HallOfFame<T> {
+    capacity: usize,
+    opponents: usize,
+    members: Vec<T>,
+}
+
+impl<T> HallOfFame<T> {
+
+    /// Constructs a `HallOfFame` holding up to `capacity` of the most recent
+    /// champions, each generation playing a sample of `opponents` of them
+    pub fn new(capacity: usize, opponents: usize) -> HallOfFame<T> {
+        HallOfFame {
+            capacity: capacity,
+            opponents: opponents,
+            members: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the members, oldest first
+    pub fn members(&self) -> &[T] {
+        &self.members
+    }
+
+    /// Replaces the members, e.g. with ones loaded from a saved state,
+    /// keeping only the most recent if there are too many
+    pub fn set_members(&mut self, mut members: Vec<T>) {
+        let excess = members.len().saturating_sub(self.capacity);
+        members.drain(..excess);
+        self.members = members;
+    }
+
+    /// Adds a champion, pushing out the oldest member when full
+    pub fn induct(&mut self, champion: T) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.members.len() == self.capacity {
+            self.members.remove(0);
+        }
+        self.members.push(champion);
+    }
+
+    /// Chooses the members the current generation plays
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Vec<&T> {
+        rand::sample(rng, self.members.iter(), self.opponents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    #[test]
+    fn test_induct() {
+        let mut hall_of_fame = HallOfFame::new(3, 2);
+
+        for champion in 0..5 {
+            hall_of_fame.induct(champion);
+        }
+        assert_eq!(&[2, 3, 4], hall_of_fame.members());
+
+        hall_of_fame.set_members(vec![7, 8, 9, 10]);
+        assert_eq!(&[8, 9, 10], hall_of_fame.members());
+
+        let mut disabled = HallOfFame::new(0, 2);
+        disabled.induct(1);
+        assert!(disabled.members().is_empty());
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut rng = StdRng::from_seed(&[3]);
+        let mut hall_of_fame = HallOfFame::new(10, 3);
+
+        hall_of_fame.induct(1);
+        assert_eq!(vec![&1], hall_of_fame.sample(&mut rng));
+
+        for champion in 2..10 {
+            hall_of_fame.induct(champion);
+        }
+
+        let mut sample = hall_of_fame.sample(&mut rng);
+        sample.sort();
+        sample.dedup();
+        assert_eq!(3, sample.len());
+    }
+}
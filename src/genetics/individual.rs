@@ -1,21 +1,53 @@
+use rand::Rng;
 use std::fmt;
+use super::Record;
 
 /// An evolutionary individual
-pub trait Individual : Send + fmt::Debug {
+pub trait Individual : Send + Clone + fmt::Debug {
+    
+    /// Settings shared by every individual of this type in an evolution
+    type Config;
     
     /// Creates a new individual
-    fn new() -> Self;     // TODO: Replace with Rand?
+    fn new<R: Rng>(config: &Self::Config, rng: &mut R) -> Self;
 
 	/// Produces a new individual by collaborating with a mate
-    fn reproduce(&self, mate: &Self) -> Self; 
+    fn reproduce<R: Rng>(&self, mate: &Self, config: &Self::Config, rng: &mut R) -> Self; 
 	
 	/// Returns a rating used to compare this individual against
 	/// others of the same type
 	fn get_rating(&self) -> i32;    // TODO: Replace with Ord?
 	
+	/// Returns the game outcomes behind the current rating, if the individual
+	/// keeps track of them
+	fn record(&self) -> Record {
+	    Record::default()
+	}
+	
+	/// Settles the rating from the games just played. Called once a
+	/// generation, before the individuals are ranked.
+	fn conclude(&mut self) {}
+	
 	/// Resets any internal counters that determine this individual's rating
 	fn reset(&mut self);
 	
 	/// Compete with another individual of the same type
-	fn compete(&mut self, other: &mut Self);
+	fn compete<R: Rng>(&mut self, other: &mut Self, rng: &mut R);
+	
+	/// Returns how different this individual is from another, used to group
+	/// similar individuals into species. Everyone is alike by default.
+	fn distance(&self, _other: &Self) -> f64 {
+	    0f64
+	}
+}
+
+/// An individual that can be scored on its own against a fixed environment
+/// rather than by competing with others
+pub trait Evaluate : Individual {
+
+    /// What the individual is scored against, shared by every thread
+    type Environment: Sync;
+
+    /// Scores the individual, updating whatever determines its rating
+    fn evaluate<R: Rng>(&mut self, environment: &Self::Environment, rng: &mut R);
 }
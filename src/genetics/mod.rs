@@ -1,5 +1,21 @@
+pub use self::competition::{Competition, Evaluation, RoundRobin};
 pub use self::evolution::Evolution;
-pub use self::individual::Individual;
+pub use self::hall_of_fame::HallOfFame;
+pub use self::individual::{Evaluate, Individual};
+pub use self::observer::Observer;
+pub use self::stats::{GenerationStats, Record, StatsFormat, StatsLog};
+pub use self::rating::{Rated, RatingSystem};
+pub use self::termination::{StopReason, Termination};
+pub use self::selection::{Selection, Truncation};
+
+pub mod competition;
+pub mod rating;
+pub mod selection;
 
 mod evolution;
-mod individual;
\ No newline at end of file
+mod hall_of_fame;
+mod individual;
+mod observer;
+mod speciation;
+mod stats;
+mod termination;
\ No newline at end of file
@@ -0,0 +1,22 @@
+use super::{GenerationStats, Individual};
+
+/// Hooks called as an `Evolution` runs. Every hook does nothing by default so
+/// an observer only implements the ones it needs.
+pub trait Observer<T: Individual> {
+
+    /// Called before a generation competes, with the complete population
+    fn on_generation_start(&mut self, _generation: u32, _individuals: &[T]) {}
+
+    /// Called once every individual has competed, with the population sorted
+    /// best first
+    fn on_competition_complete(&mut self, _generation: u32, _individuals: &[T]) {}
+
+    /// Called with the indices into the sorted population of each pair of
+    /// parents chosen to breed
+    fn on_selection(&mut self, _generation: u32, _parents: &[usize], _individuals: &[T]) {}
+
+    /// Called once a generation has been ranked, before the next one is
+    /// bred, with its statistics, the population sorted best first and the
+    /// hall of fame
+    fn on_generation_end(&mut self, _stats: &GenerationStats, _individuals: &[T], _hall_of_fame: &[T]) {}
+}
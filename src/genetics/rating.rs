@@ -0,0 +1,276 @@
+//! Elo and Glicko-2 ratings, which account for the strength of the opponents
+//! an individual happened to play.
+//!
+//! Results are collected over a rating period, typically a generation, and
+//! applied together at its end, as Glicko-2 expects.
+
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// Converts between the Glicko and Glicko-2 scales
+const GLICKO2_SCALE: f64 = 173.7178;
+
+/// The tolerance of the Glicko-2 volatility iteration
+const CONVERGENCE: f64 = 0.000001;
+
+/// How ratings are updated from game results
+#[derive(RustcEncodable, RustcDecodable, Copy, Clone, Debug, PartialEq)]
+pub enum RatingSystem {
+    /// Elo with the given K-factor, the most a single game can move a rating
+    Elo { k: f64 },
+    /// Glicko-2 with the given system constant, which limits how quickly the
+    /// volatility changes
+    Glicko2 { tau: f64 },
+}
+
+impl FromStr for RatingSystem {
+    type Err = String;
+
+    /// Parses `elo` or `glicko2`
+    fn from_str(s: &str) -> Result<RatingSystem, String> {
+        match s {
+            "elo" => Ok(RatingSystem::Elo { k: 32f64 }),
+            "glicko2" => Ok(RatingSystem::Glicko2 { tau: 0.5 }),
+            _ => Err(format!("unknown rating system '{}'", s)),
+        }
+    }
+}
+
+/// A rating on the familiar Elo scale. Elo only uses `rating`.
+#[derive(RustcEncodable, RustcDecodable, Copy, Clone, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    /// How uncertain the rating is
+    pub deviation: f64,
+    /// How erratic the individual's results are
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating {
+            rating: 1500f64,
+            deviation: 350f64,
+            volatility: 0.06,
+        }
+    }
+}
+
+/// The result of a single game
+#[derive(RustcEncodable, RustcDecodable, Copy, Clone, Debug, PartialEq)]
+pub struct Outcome {
+    /// The opponent's rating at the start of the rating period
+    pub opponent: Rating,
+    /// 1 for a win, 0.5 for a tie and 0 for a loss
+    pub score: f64,
+}
+
+impl RatingSystem {
+
+    /// Returns the rating after a rating period with the given outcomes
+    pub fn update(&self, rating: &Rating, outcomes: &[Outcome]) -> Rating {
+        match *self {
+            RatingSystem::Elo { k } => {
+                let change = outcomes.iter().fold(0f64, |sum, outcome| {
+                    let difference = outcome.opponent.rating - rating.rating;
+                    let expected = 1f64 / (1f64 + 10f64.powf(difference / 400f64));
+                    sum + outcome.score - expected
+                });
+
+                Rating { rating: rating.rating + k * change, ..*rating }
+            }
+            RatingSystem::Glicko2 { tau } => glicko2(rating, outcomes, tau),
+        }
+    }
+}
+
+/// Tracks an individual's rating and the outcomes of the current rating
+/// period
+#[derive(RustcEncodable, RustcDecodable, Clone, Debug, PartialEq)]
+pub struct Rated {
+    pub system: RatingSystem,
+    /// The rating at the start of the current period
+    pub rating: Rating,
+    outcomes: Vec<Outcome>,
+}
+
+impl Rated {
+
+    /// Starts with the default rating
+    pub fn new(system: RatingSystem) -> Rated {
+        Rated {
+            system: system,
+            rating: Rating::default(),
+            outcomes: Vec::new(),
+        }
+    }
+
+    /// Records a game against an opponent, who should be rated by the rating
+    /// they started the period with
+    pub fn record(&mut self, opponent: Rating, score: f64) {
+        self.outcomes.push(Outcome { opponent: opponent, score: score });
+    }
+
+    /// Returns the rating including the outcomes of the current period
+    pub fn current(&self) -> Rating {
+        self.system.update(&self.rating, &self.outcomes)
+    }
+
+    /// Applies the outcomes of the current period and starts a new one
+    pub fn conclude(&mut self) {
+        self.rating = self.current();
+        self.outcomes.clear();
+    }
+}
+
+/// Applies a Glicko-2 rating period, as described by Glickman in "Example
+/// of the Glicko-2 system"
+fn glicko2(rating: &Rating, outcomes: &[Outcome], tau: f64) -> Rating {
+    let mu = (rating.rating - 1500f64) / GLICKO2_SCALE;
+    let phi = rating.deviation / GLICKO2_SCALE;
+    let sigma = rating.volatility;
+
+    // Only the uncertainty grows without any games
+    if outcomes.is_empty() {
+        return Rating {
+            deviation: (phi * phi + sigma * sigma).sqrt() * GLICKO2_SCALE,
+            ..*rating
+        };
+    }
+
+    let g = |phi: f64| 1f64 / (1f64 + 3f64 * phi * phi / (PI * PI)).sqrt();
+
+    let (inverse_variance, improvement) = outcomes.iter().fold((0f64, 0f64), |(v, d), outcome| {
+        let mu_j = (outcome.opponent.rating - 1500f64) / GLICKO2_SCALE;
+        let g_j = g(outcome.opponent.deviation / GLICKO2_SCALE);
+        let expected = 1f64 / (1f64 + (-g_j * (mu - mu_j)).exp());
+
+        (v + g_j * g_j * expected * (1f64 - expected), d + g_j * (outcome.score - expected))
+    });
+
+    let variance = 1f64 / inverse_variance;
+    let delta = variance * improvement;
+
+    // Find the new volatility with the Illinois algorithm
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let denominator = phi * phi + variance + ex;
+
+        ex * (delta * delta - phi * phi - variance - ex) / (2f64 * denominator * denominator) -
+            (x - a) / (tau * tau)
+    };
+
+    let mut lower = a;
+    let mut upper = if delta * delta > phi * phi + variance {
+        (delta * delta - phi * phi - variance).ln()
+    } else {
+        let mut k = 1f64;
+        while f(a - k * tau) < 0f64 {
+            k += 1f64;
+        }
+        a - k * tau
+    };
+
+    let mut f_lower = f(lower);
+    let mut f_upper = f(upper);
+
+    while (upper - lower).abs() > CONVERGENCE {
+        let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_c = f(c);
+
+        if f_c * f_upper <= 0f64 {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2f64;
+        }
+
+        upper = c;
+        f_upper = f_c;
+    }
+
+    let new_sigma = (lower / 2f64).exp();
+    let phi_star = (phi * phi + new_sigma * new_sigma).sqrt();
+    let new_phi = 1f64 / (1f64 / (phi_star * phi_star) + 1f64 / variance).sqrt();
+    let new_mu = mu + new_phi * new_phi * improvement;
+
+    Rating {
+        rating: new_mu * GLICKO2_SCALE + 1500f64,
+        deviation: new_phi * GLICKO2_SCALE,
+        volatility: new_sigma,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: f64, tolerance: f64) {
+        assert!((expected - actual).abs() < tolerance, "expected {}, found {}", expected, actual);
+    }
+
+    fn opponent(rating: f64, deviation: f64) -> Rating {
+        Rating { rating: rating, deviation: deviation, volatility: 0.06 }
+    }
+
+    #[test]
+    fn test_glicko2_example() {
+        // The worked example from Glickman's paper
+        let rating = Rating { rating: 1500f64, deviation: 200f64, volatility: 0.06 };
+        let outcomes = [Outcome { opponent: opponent(1400f64, 30f64), score: 1f64 },
+                        Outcome { opponent: opponent(1550f64, 100f64), score: 0f64 },
+                        Outcome { opponent: opponent(1700f64, 300f64), score: 0f64 }];
+
+        let updated = RatingSystem::Glicko2 { tau: 0.5 }.update(&rating, &outcomes);
+
+        assert_close(1464.06, updated.rating, 0.01);
+        assert_close(151.52, updated.deviation, 0.01);
+        assert_close(0.05999, updated.volatility, 0.00001);
+    }
+
+    #[test]
+    fn test_glicko2_no_games() {
+        let rating = Rating { rating: 1600f64, deviation: 50f64, volatility: 0.06 };
+        let updated = RatingSystem::Glicko2 { tau: 0.5 }.update(&rating, &[]);
+
+        assert_eq!(1600f64, updated.rating);
+        assert!(updated.deviation > 50f64);
+    }
+
+    #[test]
+    fn test_elo() {
+        let system = RatingSystem::Elo { k: 32f64 };
+        let rating = Rating::default();
+
+        // Equal players move by half the K-factor
+        let won = system.update(&rating, &[Outcome { opponent: rating, score: 1f64 }]);
+        assert_close(1516f64, won.rating, 1e-9);
+
+        // Beating a much weaker player gains little
+        let weak = opponent(1100f64, 350f64);
+        let won = system.update(&rating, &[Outcome { opponent: weak, score: 1f64 }]);
+        assert_close(1502.9, won.rating, 0.1);
+    }
+
+    #[test]
+    fn test_rated() {
+        let mut rated = Rated::new(RatingSystem::Elo { k: 32f64 });
+
+        rated.record(Rating::default(), 0.5);
+        rated.record(Rating::default(), 1f64);
+        assert_eq!(1500f64, rated.rating.rating);
+        assert_close(1516f64, rated.current().rating, 1e-9);
+
+        rated.conclude();
+        assert_close(1516f64, rated.rating.rating, 1e-9);
+        assert_eq!(rated.rating, rated.current());
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(RatingSystem::Elo { k: 32f64 }), "elo".parse());
+        assert_eq!(Ok(RatingSystem::Glicko2 { tau: 0.5 }), "glicko2".parse());
+        assert!("trueskill".parse::<RatingSystem>().is_err());
+    }
+}
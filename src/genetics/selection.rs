@@ -0,0 +1,248 @@
+use rand::Rng;
+
+/// Chooses which individuals become the parents of the next generation
+pub trait Selection: Send {
+
+    /// Returns the indices of `count` parents chosen according to their
+    /// ratings. An individual may be chosen more than once.
+    fn select(&self, ratings: &[i32], count: usize, rng: &mut Rng) -> Vec<usize>;
+}
+
+/// Parents are chosen uniformly from the best `fraction` of individuals
+pub struct Truncation {
+    pub fraction: f32,
+}
+
+/// Each parent is the best of `size` individuals picked at random
+pub struct Tournament {
+    pub size: usize,
+}
+
+/// Fitness proportionate selection, where each spin of the wheel picks one
+/// parent. Ratings are shifted so that the worst individual has no chance.
+pub struct Roulette;
+
+/// Linear ranking, where the chance of being picked depends only on an
+/// individual's position. `pressure` is between 1 (uniform) and 2 (the
+/// worst is never picked).
+pub struct Rank {
+    pub pressure: f32,
+}
+
+/// Fitness proportionate selection with a single spin of a wheel with
+/// `count` evenly spaced pointers, which keeps the number of times each
+/// individual is picked close to its expected value
+pub struct StochasticUniversal;
+
+impl Selection for Truncation {
+    fn select(&self, ratings: &[i32], count: usize, rng: &mut Rng) -> Vec<usize> {
+        let ranked = ranked(ratings);
+        let num_best = ((ratings.len() as f32 * self.fraction) as usize)
+            .max(1)
+            .min(ratings.len());
+
+        (0..count)
+            .map(|_| ranked[ranked.len() - 1 - gen_index(rng, num_best)])
+            .collect()
+    }
+}
+
+impl Selection for Tournament {
+    fn select(&self, ratings: &[i32], count: usize, rng: &mut Rng) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                let mut best = gen_index(rng, ratings.len());
+
+                for _ in 1..self.size {
+                    let contender = gen_index(rng, ratings.len());
+
+                    if ratings[contender] > ratings[best] {
+                        best = contender;
+                    }
+                }
+
+                best
+            })
+            .collect()
+    }
+}
+
+impl Selection for Roulette {
+    fn select(&self, ratings: &[i32], count: usize, rng: &mut Rng) -> Vec<usize> {
+        let weights = shifted(ratings);
+
+        (0..count)
+            .map(|_| spin(&weights, gen_f64(rng) * total(&weights)))
+            .collect()
+    }
+}
+
+impl Selection for Rank {
+    fn select(&self, ratings: &[i32], count: usize, rng: &mut Rng) -> Vec<usize> {
+        let ranked = ranked(ratings);
+        let n = ratings.len() as f64;
+        let pressure = self.pressure as f64;
+
+        // Weight of the individual at each rank, counting up from the worst
+        let weights = (0..ranked.len())
+            .map(|rank| {
+                if n > 1f64 {
+                    (2f64 - pressure) / n + 2f64 * rank as f64 * (pressure - 1f64) / (n * (n - 1f64))
+                } else {
+                    1f64
+                }
+            })
+            .collect::<Vec<_>>();
+
+        (0..count)
+            .map(|_| ranked[spin(&weights, gen_f64(rng) * total(&weights))])
+            .collect()
+    }
+}
+
+impl Selection for StochasticUniversal {
+    fn select(&self, ratings: &[i32], count: usize, mut rng: &mut Rng) -> Vec<usize> {
+        let weights = shifted(ratings);
+        let spacing = total(&weights) / count as f64;
+        let start = gen_f64(rng) * spacing;
+
+        let mut parents = (0..count)
+            .map(|i| spin(&weights, start + i as f64 * spacing))
+            .collect::<Vec<_>>();
+
+        // The pointers pick parents in order, so mix them up for pairing
+        Rng::shuffle(&mut rng, &mut parents);
+        parents
+    }
+}
+
+/// Parses a selection strategy by name: `truncation`, `tournament`,
+/// `roulette`, `rank` or `sus`. Truncation picks uniformly from every
+/// individual it's given, since the survivors have already been cut.
+pub fn parse(name: &str, tournament_size: usize) -> Result<Box<Selection>, String> {
+    match name {
+        "truncation" => Ok(Box::new(Truncation { fraction: 1f32 })),
+        "tournament" => Ok(Box::new(Tournament { size: tournament_size })),
+        "roulette" => Ok(Box::new(Roulette)),
+        "rank" => Ok(Box::new(Rank { pressure: 1.5 })),
+        "sus" => Ok(Box::new(StochasticUniversal)),
+        _ => Err(format!("unknown selection '{}'", name)),
+    }
+}
+
+/// Returns the indices of the ratings ordered from worst to best
+fn ranked(ratings: &[i32]) -> Vec<usize> {
+    let mut ranked = (0..ratings.len()).collect::<Vec<_>>();
+
+    ranked.sort_by_key(|&i| ratings[i]);
+    ranked
+}
+
+/// Shifts the ratings so the worst is zero. If every rating is the same they
+/// are all given an equal weight instead.
+fn shifted(ratings: &[i32]) -> Vec<f64> {
+    let min = ratings.iter().cloned().min().unwrap_or(0);
+    let max = ratings.iter().cloned().max().unwrap_or(0);
+
+    ratings.iter()
+        .map(|&rating| if min == max { 1f64 } else { (rating - min) as f64 })
+        .collect()
+}
+
+fn total(weights: &[f64]) -> f64 {
+    weights.iter().fold(0f64, |sum, w| sum + w)
+}
+
+/// Returns the index of the weight that `point` falls within
+fn spin(weights: &[f64], mut point: f64) -> usize {
+    for (i, &weight) in weights.iter().enumerate() {
+        if point < weight {
+            return i;
+        }
+        point -= weight;
+    }
+
+    // Rounding may leave the point just past the end
+    weights.iter().rposition(|&weight| weight > 0f64).unwrap_or(0)
+}
+
+fn gen_index(mut rng: &mut Rng, len: usize) -> usize {
+    Rng::gen_range(&mut rng, 0, len)
+}
+
+fn gen_f64(mut rng: &mut Rng) -> f64 {
+    Rng::gen(&mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    const RATINGS: [i32; 6] = [3, -2, 7, 0, 7, 1];
+
+    fn counts(selection: &Selection, ratings: &[i32], count: usize) -> Vec<usize> {
+        let mut rng = StdRng::from_seed(&[11]);
+        let mut counts = vec![0; ratings.len()];
+
+        for i in selection.select(ratings, count, &mut rng) {
+            counts[i] += 1;
+        }
+
+        assert_eq!(count, counts.iter().fold(0, |sum, c| sum + c));
+        counts
+    }
+
+    #[test]
+    fn test_truncation() {
+        let counts = counts(&Truncation { fraction: 0.5 }, &RATINGS, 300);
+
+        // Only the top three are ever picked
+        assert_eq!(vec![true, false, true, false, true, false],
+                   counts.iter().map(|&c| c > 0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_tournament() {
+        // With every individual in each tournament the best always wins
+        assert_eq!(vec![0, 20, 0], counts(&Tournament { size: 100 }, &[1, 5, 2], 20));
+
+        // A single entrant makes selection uniform
+        assert!(counts(&Tournament { size: 1 }, &RATINGS, 600).iter().all(|&c| c > 50));
+    }
+
+    #[test]
+    fn test_roulette() {
+        let weighted = counts(&Roulette, &RATINGS, 1000);
+
+        // The worst has no weight and the best are picked most
+        assert_eq!(0, weighted[1]);
+        assert!(weighted[2] > weighted[0] && weighted[0] > weighted[5]);
+
+        // Equal ratings are picked uniformly
+        let equal = counts(&Roulette, &[4, 4], 100);
+        assert!(equal[0] > 0 && equal[1] > 0);
+    }
+
+    #[test]
+    fn test_rank() {
+        let counts = counts(&Rank { pressure: 2.0 }, &[10, -5, 0], 1000);
+
+        assert_eq!(0, counts[1]);
+        assert!(counts[0] > counts[2]);
+    }
+
+    #[test]
+    fn test_stochastic_universal() {
+        // Shifted weights of 0, 1 and 3 give exactly the expected counts
+        let counts = counts(&StochasticUniversal, &[2, 3, 5], 4);
+        assert_eq!(vec![0, 1, 3], counts);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("tournament", 3).is_ok());
+        assert!(parse("sus", 3).is_ok());
+        assert!(parse("lottery", 3).is_err());
+    }
+}
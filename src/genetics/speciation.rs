@@ -0,0 +1,110 @@
+use super::Individual;
+
+/// Groups the individuals, which must be sorted best first, into species.
+/// Each individual joins the first species whose best member is within
+/// `threshold` of it, or starts a new one. Returns the indices of each
+/// species' members, best first.
+pub fn speciate<T: Individual>(individuals: &[T], threshold: f64) -> Vec<Vec<usize>> {
+    let mut species: Vec<Vec<usize>> = Vec::new();
+
+    for (i, individual) in individuals.iter().enumerate() {
+        let found = species.iter()
+            .position(|members| individuals[members[0]].distance(individual) <= threshold);
+
+        match found {
+            Some(s) => species[s].push(i),
+            None => species.push(vec![i]),
+        }
+    }
+
+    species
+}
+
+/// Splits `total` children between the species in proportion to their
+/// shared fitness, the mean of their members' ratings, so that one large
+/// species can't take over the population. Ratings are shifted so that the
+/// worst is one before sharing.
+pub fn allocate(species: &[Vec<usize>], ratings: &[i32], total: usize) -> Vec<usize> {
+    let min = ratings.iter().cloned().min().unwrap_or(0) as f64;
+
+    let shares = species.iter()
+        .map(|members| {
+            let sum = members.iter().fold(0f64, |sum, &i| sum + ratings[i] as f64 - min + 1f64);
+            sum / members.len() as f64
+        })
+        .collect::<Vec<_>>();
+    let total_share = shares.iter().fold(0f64, |sum, share| sum + share);
+
+    // Give each species its whole number of children, then hand out the rest
+    // by the largest remainders
+    let exact = shares.iter()
+        .map(|share| total as f64 * share / total_share)
+        .collect::<Vec<_>>();
+    let mut counts = exact.iter().map(|&e| e.floor() as usize).collect::<Vec<_>>();
+
+    let mut by_remainder = (0..species.len()).collect::<Vec<_>>();
+    by_remainder.sort_by(|&a, &b| {
+        (exact[b] - exact[b].floor()).partial_cmp(&(exact[a] - exact[a].floor())).unwrap()
+    });
+
+    let allocated = counts.iter().fold(0, |sum, count| sum + count);
+    for &s in by_remainder.iter().cycle().take(total - allocated) {
+        counts[s] += 1;
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// An individual identified by a position on a line
+    #[derive(Clone, Debug)]
+    struct Point(f64);
+
+    impl Individual for Point {
+        type Config = ();
+
+        fn new<R: Rng>(_config: &(), rng: &mut R) -> Point {
+            Point(rng.gen())
+        }
+
+        fn reproduce<R: Rng>(&self, mate: &Point, _config: &(), _rng: &mut R) -> Point {
+            Point((self.0 + mate.0) / 2f64)
+        }
+
+        fn get_rating(&self) -> i32 {
+            0
+        }
+
+        fn reset(&mut self) {}
+
+        fn compete<R: Rng>(&mut self, _other: &mut Point, _rng: &mut R) {}
+
+        fn distance(&self, other: &Point) -> f64 {
+            (self.0 - other.0).abs()
+        }
+    }
+
+    #[test]
+    fn test_speciate() {
+        let points = [Point(0f64), Point(10f64), Point(0.5), Point(10.8), Point(1.2), Point(20f64)];
+
+        assert_eq!(vec![vec![0, 2], vec![1, 3], vec![4], vec![5]], speciate(&points, 1f64));
+        assert_eq!(vec![vec![0, 1, 2, 3, 4, 5]], speciate(&points, 100f64));
+    }
+
+    #[test]
+    fn test_allocate() {
+        // Shifted ratings of 3 and 3 share to 3, and 1 shares to 1
+        let species = vec![vec![0, 1], vec![2]];
+        assert_eq!(vec![6, 2], allocate(&species, &[5, 5, 3], 8));
+
+        // Remainders go to the closest species
+        assert_eq!(vec![5, 2], allocate(&species, &[5, 5, 3], 7));
+
+        assert_eq!(vec![4], allocate(&[vec![0, 1, 2]], &[-4, 0, 9], 4));
+    }
+}
@@ -0,0 +1,232 @@
+use rustc_serialize::json;
+use super::{Individual, Observer};
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::str::FromStr;
+
+/// Game outcomes totalled over an individual's competitions
+#[derive(RustcEncodable, RustcDecodable, Copy, Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub wins: u32,
+    pub ties: u32,
+    pub losses: u32,
+    pub mistakes: u32,
+}
+
+/// A summary of the ratings of one generation after it has competed
+#[derive(RustcEncodable, RustcDecodable, Clone, Debug, PartialEq)]
+pub struct GenerationStats {
+    pub generation: u32,
+    pub best: i32,
+    pub mean: f64,
+    pub median: f64,
+    pub worst: i32,
+    pub std_dev: f64,
+    pub wins: u32,
+    pub ties: u32,
+    pub losses: u32,
+    pub mistakes: u32,
+    /// The number of species the generation was grouped into
+    pub species: usize,
+    /// Wall time taken by the generation to compete and be ranked
+    pub seconds: f64,
+}
+
+impl GenerationStats {
+
+    /// Summarises a generation from its ratings, which must be sorted best
+    /// first, and the records of its individuals
+    pub fn new(generation: u32, ratings: &[i32], records: &[Record]) -> GenerationStats {
+        assert!(!ratings.is_empty());
+
+        let count = ratings.len() as f64;
+        let mean = ratings.iter().fold(0f64, |sum, &r| sum + r as f64) / count;
+        let variance = ratings.iter()
+            .fold(0f64, |sum, &r| sum + (r as f64 - mean) * (r as f64 - mean)) / count;
+
+        let middle = ratings.len() / 2;
+        let median = if ratings.len() % 2 == 0 {
+            (ratings[middle - 1] as f64 + ratings[middle] as f64) / 2f64
+        } else {
+            ratings[middle] as f64
+        };
+
+        let total = records.iter().fold(Record::default(), |total, record| {
+            Record {
+                wins: total.wins + record.wins,
+                ties: total.ties + record.ties,
+                losses: total.losses + record.losses,
+                mistakes: total.mistakes + record.mistakes,
+            }
+        });
+
+        GenerationStats {
+            generation: generation,
+            best: ratings[0],
+            mean: mean,
+            median: median,
+            worst: ratings[ratings.len() - 1],
+            std_dev: variance.sqrt(),
+            wins: total.wins,
+            ties: total.ties,
+            losses: total.losses,
+            mistakes: total.mistakes,
+            species: 1,
+            seconds: 0f64,
+        }
+    }
+}
+
+/// The file format of a `StatsLog`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatsFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl FromStr for StatsFormat {
+    type Err = String;
+
+    /// Parses `csv` or `json`
+    fn from_str(s: &str) -> Result<StatsFormat, String> {
+        match s {
+            "csv" => Ok(StatsFormat::Csv),
+            "json" => Ok(StatsFormat::JsonLines),
+            _ => Err(format!("unknown statistics format '{}'", s)),
+        }
+    }
+}
+
+const CSV_HEADER: &'static str =
+    "generation,best,mean,median,worst,std_dev,wins,ties,losses,mistakes,species,seconds";
+
+/// Writes the statistics of each generation as it finishes
+pub struct StatsLog {
+    writer: Box<Write + Send>,
+    format: StatsFormat,
+    started: bool,
+}
+
+impl StatsLog {
+
+    /// Creates a log that truncates and writes to the file at the given path
+    pub fn create(path: &str, format: StatsFormat) -> io::Result<StatsLog> {
+        let file = fs::File::create(path)?;
+        Ok(StatsLog::new(Box::new(io::BufWriter::new(file)), format))
+    }
+
+    /// Creates a log that writes to any writer
+    pub fn new(writer: Box<Write + Send>, format: StatsFormat) -> StatsLog {
+        StatsLog {
+            writer: writer,
+            format: format,
+            started: false,
+        }
+    }
+
+    /// Appends the statistics of a generation
+    pub fn write(&mut self, stats: &GenerationStats) -> io::Result<()> {
+        match self.format {
+            StatsFormat::Csv => {
+                if !self.started {
+                    writeln!(self.writer, "{}", CSV_HEADER)?;
+                }
+                writeln!(self.writer, "{},{},{},{},{},{},{},{},{},{},{},{}",
+                    stats.generation, stats.best, stats.mean, stats.median, stats.worst,
+                    stats.std_dev, stats.wins, stats.ties, stats.losses, stats.mistakes,
+                    stats.species, stats.seconds)?;
+            }
+            StatsFormat::JsonLines => {
+                let line = json::encode(stats)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                writeln!(self.writer, "{}", line)?;
+            }
+        }
+
+        self.started = true;
+
+        // Flush every generation so the log can be watched while evolving
+        self.writer.flush()
+    }
+}
+
+impl<T: Individual> Observer<T> for StatsLog {
+    fn on_generation_end(&mut self, stats: &GenerationStats, _individuals: &[T], _hall_of_fame: &[T]) {
+        if let Err(e) = self.write(stats) {
+            warn!("Unable to write statistics: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A writer whose contents can be inspected after the log takes it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn example_stats() -> GenerationStats {
+        let records = [Record { wins: 3, ties: 1, losses: 0, mistakes: 2 },
+                       Record { wins: 0, ties: 1, losses: 3, mistakes: 5 }];
+
+        GenerationStats::new(4, &[9, 5, 3, -1], &records)
+    }
+
+    #[test]
+    fn test_new() {
+        let stats = example_stats();
+
+        assert_eq!((9, -1), (stats.best, stats.worst));
+        assert_eq!(4f64, stats.mean);
+        assert_eq!(4f64, stats.median);
+        assert_eq!(13f64.sqrt(), stats.std_dev);
+        assert_eq!((3, 2, 3, 7), (stats.wins, stats.ties, stats.losses, stats.mistakes));
+
+        assert_eq!(5f64, GenerationStats::new(0, &[7, 5, 1], &[]).median);
+    }
+
+    #[test]
+    fn test_csv() {
+        let output = Shared::default();
+        let mut log = StatsLog::new(Box::new(output.clone()), StatsFormat::Csv);
+
+        log.write(&example_stats()).unwrap();
+        log.write(&example_stats()).unwrap();
+
+        let contents = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+
+        assert_eq!(vec![CSV_HEADER, "4,9,4,4,-1,3.605551275463989,3,2,3,7,1,0", lines[1]], lines);
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let output = Shared::default();
+        let mut log = StatsLog::new(Box::new(output.clone()), StatsFormat::JsonLines);
+
+        log.write(&example_stats()).unwrap();
+        log.write(&example_stats()).unwrap();
+
+        let contents = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+
+        for line in contents.lines() {
+            assert_eq!(example_stats(), json::decode::<GenerationStats>(line).unwrap());
+        }
+        assert_eq!(2, contents.lines().count());
+    }
+}
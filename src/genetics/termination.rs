@@ -0,0 +1,210 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use super::GenerationStats;
+
+/// The reason an evolution stopped
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StopReason {
+    /// The maximum number of generations was reached
+    Generations(u32),
+    /// The best individual reached the target rating
+    TargetRating(i32),
+    /// The best individual reached the target score against the reference
+    TargetScore(f64),
+    /// The best rating didn't improve for this many generations
+    Stagnation(u32),
+    /// The time limit ran out
+    TimeLimit(Duration),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StopReason::Generations(n) => write!(f, "reached {} generations", n),
+            StopReason::TargetRating(rating) => write!(f, "reached a rating of {}", rating),
+            StopReason::TargetScore(score) => write!(f, "scored {} against the reference", score),
+            StopReason::Stagnation(n) => write!(f, "no improvement for {} generations", n),
+            StopReason::TimeLimit(limit) => write!(f, "ran out of time after {}s", limit.as_secs()),
+        }
+    }
+}
+
+/// The conditions that stop `Evolution::evolve_until`. With no conditions set
+/// the evolution never stops.
+pub struct Termination<T> {
+    max_generations: Option<u32>,
+    target_rating: Option<i32>,
+    target_score: Option<(f64, Box<FnMut(&T) -> f64>)>,
+    stagnation: Option<u32>,
+    time_limit: Option<Duration>,
+    /// The generations checked so far
+    generations: u32,
+    /// The best rating of any generation so far
+    best_rating: Option<i32>,
+    /// The generations since the best rating last improved
+    stale: u32,
+}
+
+impl<T> Termination<T> {
+
+    pub fn new() -> Termination<T> {
+        Termination {
+            max_generations: None,
+            target_rating: None,
+            target_score: None,
+            stagnation: None,
+            time_limit: None,
+            generations: 0,
+            best_rating: None,
+            stale: 0,
+        }
+    }
+
+    /// Stops after this many generations
+    pub fn generations(mut self, generations: u32) -> Termination<T> {
+        self.max_generations = Some(generations);
+        self
+    }
+
+    /// Stops once the best individual of a generation reaches this rating
+    pub fn target_rating(mut self, rating: i32) -> Termination<T> {
+        self.target_rating = Some(rating);
+        self
+    }
+
+    /// Stops once `score` gives the best individual of a generation at least
+    /// `target`. The score is typically the result of games against a fixed
+    /// reference opponent.
+    pub fn target_score<F>(mut self, target: f64, score: F) -> Termination<T>
+        where F: FnMut(&T) -> f64 + 'static
+    {
+        self.target_score = Some((target, Box::new(score)));
+        self
+    }
+
+    /// Stops once the best rating hasn't improved for this many generations
+    pub fn stagnation(mut self, generations: u32) -> Termination<T> {
+        assert!(generations > 0);
+        self.stagnation = Some(generations);
+        self
+    }
+
+    /// Stops after the first generation to finish once this much time has
+    /// passed
+    pub fn time_limit(mut self, limit: Duration) -> Termination<T> {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Checks the conditions after a generation has competed, given its
+    /// statistics and best individual. Each generation is checked once, in
+    /// order, so only what the conditions need is kept between them.
+    pub fn check(&mut self,
+                 latest: &GenerationStats,
+                 best: &T,
+                 started: Instant)
+                 -> Option<StopReason> {
+        self.generations += 1;
+
+        if self.best_rating.map_or(true, |rating| latest.best > rating) {
+            self.best_rating = Some(latest.best);
+            self.stale = 0;
+        } else {
+            self.stale += 1;
+        }
+
+        if let Some(rating) = self.target_rating {
+            if latest.best >= rating {
+                return Some(StopReason::TargetRating(latest.best));
+            }
+        }
+
+        if let Some((target, ref mut score)) = self.target_score {
+            let score = score(best);
+
+            if score >= target {
+                return Some(StopReason::TargetScore(score));
+            }
+        }
+
+        // The best rating of the last `limit` generations is no better than
+        // the one before them
+        if let Some(limit) = self.stagnation {
+            if self.stale >= limit {
+                return Some(StopReason::Stagnation(limit));
+            }
+        }
+
+        if let Some(limit) = self.time_limit {
+            if started.elapsed() >= limit {
+                return Some(StopReason::TimeLimit(limit));
+            }
+        }
+
+        if let Some(generations) = self.max_generations {
+            if self.generations >= generations {
+                return Some(StopReason::Generations(generations));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks a generation with each best rating in turn and returns the
+    /// result of the last
+    fn check_all<T>(termination: &mut Termination<T>, best: &[i32], individual: &T) -> Option<StopReason> {
+        best.iter()
+            .enumerate()
+            .map(|(generation, &rating)| {
+                let stats = GenerationStats::new(generation as u32, &[rating], &[]);
+                termination.check(&stats, individual, Instant::now())
+            })
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_no_conditions() {
+        let mut termination = Termination::<()>::new();
+
+        assert_eq!(None, check_all(&mut termination, &[1, 2, 3], &()));
+    }
+
+    #[test]
+    fn test_generations_and_rating() {
+        let conditions = || Termination::<()>::new().generations(3).target_rating(10);
+
+        assert_eq!(None, check_all(&mut conditions(), &[1, 2], &()));
+        assert_eq!(Some(StopReason::Generations(3)), check_all(&mut conditions(), &[1, 2, 3], &()));
+        assert_eq!(Some(StopReason::TargetRating(12)), check_all(&mut conditions(), &[1, 12], &()));
+    }
+
+    #[test]
+    fn test_target_score() {
+        let mut termination = Termination::<f64>::new().target_score(0.75, |&score| score);
+
+        assert_eq!(None, check_all(&mut termination, &[0], &0.5));
+        assert_eq!(Some(StopReason::TargetScore(0.8)), check_all(&mut termination, &[0], &0.8));
+    }
+
+    #[test]
+    fn test_stagnation() {
+        let conditions = || Termination::<()>::new().stagnation(2);
+
+        assert_eq!(None, check_all(&mut conditions(), &[5, 4], &()));
+        assert_eq!(None, check_all(&mut conditions(), &[1, 5, 4, 6], &()));
+        assert_eq!(Some(StopReason::Stagnation(2)), check_all(&mut conditions(), &[1, 5, 4, 5], &()));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut termination = Termination::<()>::new().time_limit(Duration::from_secs(0));
+
+        assert_eq!(Some(StopReason::TimeLimit(Duration::from_secs(0))), check_all(&mut termination, &[0], &()));
+    }
+}
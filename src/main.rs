@@ -7,6 +7,7 @@ extern crate rand;
 extern crate rustc_serialize;
 extern crate scoped_threadpool;
 
+mod benchmark;
 mod game;
 mod genetics;
 mod neural;
@@ -14,61 +15,376 @@ mod persistance;
 
 use game::*;
 use genetics::Evolution;
+use rand::{SeedableRng, StdRng};
+use rustc_serialize::json;
+use genetics::Individual;
+use std::fs;
+use std::io::Write;
+use std::process;
+use std::time::Duration;
 
 fn main() {
     let args = get_args();
     
     let mut human = HumanPlayer::new();
+    
+    // Pick a seed if one wasn't given so that the run can be repeated
+    let seed = args.seed.unwrap_or_else(|| rand::random::<u32>() as usize);
+    println!("Seed: {}", seed);
+    
+    let mut rng = StdRng::from_seed(&[seed]);
+
+	let mut opponent = args.opponent.as_ref().map(|spec| {
+		let opponent = Opponent::parse(spec, seed).and_then(|opponent| {
+			if args.guided { guide(opponent) } else { Ok(opponent) }
+		});
+
+		opponent.unwrap_or_else(|e| {
+			println!("Invalid opponent: {}", e);
+			process::exit(1);
+		})
+	});
+
+	if let Some(ref benchmark) = args.benchmark {
+		run_benchmark(benchmark, seed, &mut rng);
+		return;
+	}
+
+	// A human playing a fixed opponent doesn't need a network
+	if args.human && args.examples.is_none() {
+		if let Some(ref mut opponent) = opponent {
+			play_human(&mut human, opponent, args.human_first, &mut rng);
+			return;
+		}
+	}
+
+	// Training data from perfect play
+	if let Some(ref file_path) = args.generate_examples {
+		let examples = MinimaxPlayer::new().examples();
+
+		match neural::save_examples(file_path, &examples) {
+			Ok(()) => println!("Saved {} examples to {}", examples.len(), file_path),
+			Err(e) => {
+				println!("Unable to save examples to {}: {}", file_path, e);
+				process::exit(1);
+			}
+		}
+		return;
+	}
 
 	// Machine Learning
 	if let Some(ref file_path) = args.examples {
 		println!("Loading Examples...");
 
-		let examples = neural::load_examples(file_path).unwrap();
+		let mut net = neural::Network::with_activations(&[9, 9, 9],
+		                                                &[neural::Activation::Sigmoid; 2],
+		                                                &mut rng);
+
+		let examples = match neural::load_examples(file_path, &net) {
+			Ok(examples) => examples,
+			Err(e) => {
+				println!("Unable to load examples from {}: {}", file_path, e);
+				process::exit(1);
+			}
+		};
 
-		let mut net = neural::Network::with_dimensions(&[9, 9, 9]);
 		println!("{:?}", net);
 		
-		let trainer = neural::Trainer::new(net, &examples);
+		let trainer = neural::Trainer::new(net, &examples)
+			.learning_rate(args.learning_rate)
+			.batch_size(args.batch_size)
+			.epochs(args.epochs)
+			.target_loss(args.target_loss);
 
 		net = trainer.train();
 		println!("{:?}", net);
 
 		if args.human {
 			let mut ai = AiPlayer::with_network(net);
-			play_game(&mut ai, &mut human);
+			play_human(&mut human, &mut ai, args.human_first, &mut rng);
 		}
 
 	} else {
 		// Evolution
-		let mut evolution = Evolution::<AiPlayer>::new(args.num_players);
+		let config = AiConfig {
+			crossover: args.crossover,
+			mutation: args.mutation,
+			rating: args.rating,
+		};
+
+		// A fixed random network to benchmark against so that runs can be compared
+		let reference = AiPlayer::new(&AiConfig::default(), &mut StdRng::from_seed(&[seed, 1]));
+
+		if args.rating.is_some() && (args.coverage || args.evaluate.is_some()) {
+			println!("Ratings need games, so they can't be used when evaluating");
+			process::exit(1);
+		}
+
+		let competition: Box<genetics::Competition<AiPlayer>> = if args.coverage {
+			Box::new(genetics::Evaluation { environment: AiEnvironment::Coverage(Coverage::new()) })
+		} else if let Some(ref file_path) = args.evaluate {
+			match neural::load_examples(file_path, &reference.neural_net) {
+				Ok(examples) => Box::new(genetics::Evaluation { environment: AiEnvironment::Examples(examples) }),
+				Err(e) => {
+					println!("Unable to load examples from {}: {}", file_path, e);
+					process::exit(1);
+				}
+			}
+		} else {
+			let benchmark = reference.neural_net.clone();
+			let competition = genetics::competition::parse(&args.competition, move || {
+				AiPlayer::with_network(benchmark.clone())
+			});
+
+			competition.unwrap_or_else(|e| {
+				println!("Invalid competition: {}", e);
+				process::exit(1);
+			})
+		};
+
+		let mut builder = Evolution::<AiPlayer>::builder(args.num_players, config)
+			.seed(seed)
+			.selection(args.selection)
+			.competition(competition)
+			.survival_rate(args.survival_rate)
+			.immigrant_rate(args.immigrant_rate)
+			.hall_of_fame(args.hall_of_fame, args.hall_of_fame_opponents);
+
+		if let Some(elites) = args.elites {
+			builder = builder.elites(elites);
+		}
+
+		if let Some(threshold) = args.speciation {
+			builder = builder.speciation(threshold);
+		}
+
+		if let Some(ref file_path) = args.stats {
+			match genetics::StatsLog::create(file_path, args.stats_format) {
+				Ok(log) => builder = builder.log(log),
+				Err(e) => {
+					println!("Unable to create {}: {}", file_path, e);
+					process::exit(1);
+				}
+			}
+		}
 
-		if let Some(players) = persistance::load() {
+		let mut evolution = match builder.build() {
+			Ok(evolution) => evolution,
+			Err(e) => {
+				println!("Unable to start evolution: {}", e);
+				process::exit(1);
+			}
+		};
+
+		if let Some(mut state) = persistance::load() {
 			println!("Existing state loaded!");
-			evolution.individuals = players
-		}
-
-		loop {
-			evolution.evolve(100);
-			
-			persistance::save(&evolution.individuals).unwrap();
-			
-			if args.human {
-				let best = &mut evolution.individuals[0];
-			
-				play_game(best, &mut human);
+
+			// Players saved without a rating, or with another system's,
+			// start from the default one
+			for player in state.players.iter_mut().chain(state.hall_of_fame.iter_mut()) {
+				player.rated = match (player.rated.take(), args.rating) {
+					(Some(rated), Some(system)) if rated.system == system => Some(rated),
+					(_, system) => system.map(genetics::Rated::new),
+				};
 			}
+
+			evolution.individuals = state.players;
+			evolution.set_hall_of_fame(state.hall_of_fame);
+		}
+
+		evolution.add_observer(Box::new(Progress));
+		evolution.add_observer(Box::new(persistance::Checkpoint::new(100)));
+
+		if args.human {
+			evolution.add_observer(Box::new(HumanMatch {
+				interval: 100,
+				human: human,
+				human_first: args.human_first,
+				rng: rng,
+			}));
+		}
+
+		let mut termination = genetics::Termination::new();
+
+		if let Some(generations) = args.generations {
+			termination = termination.generations(generations);
+		}
+
+		if let Some(rating) = args.target_rating {
+			termination = termination.target_rating(rating);
+		}
+
+		if let Some(target) = args.target_score {
+			let mut score_rng = StdRng::from_seed(&[seed, 2]);
+			let mut opponent = opponent.unwrap_or_else(|| Opponent::Heuristic(HeuristicPlayer::new(seed)));
+
+			termination = termination.target_score(target, move |best: &AiPlayer| {
+				best.score_against(&mut opponent, 100, &mut score_rng)
+			});
+		}
+
+		if let Some(generations) = args.stagnation {
+			termination = termination.stagnation(generations);
+		}
+
+		if let Some(seconds) = args.time_limit {
+			termination = termination.time_limit(Duration::from_secs(seconds));
 		}
+
+		let reason = evolution.evolve_until(termination);
+		println!("Stopped: {}", reason);
+
+		persistance::save(&evolution.individuals, evolution.hall_of_fame()).unwrap();
 	}
 
     
 }
 
+/// Plays the saved players against a reference opponent and reports how
+/// they did
+fn run_benchmark(args: &BenchmarkArgs, seed: usize, rng: &mut StdRng) {
+	let state = match args.file {
+		Some(ref file_path) => persistance::load_from(file_path),
+		None => persistance::load(),
+	};
+
+	let players = match state {
+		Some(state) => state.players,
+		None => {
+			println!("No saved players to benchmark");
+			process::exit(1);
+		}
+	};
+
+	let mut opponent = Opponent::parse(&args.opponent, seed).unwrap_or_else(|e| {
+		println!("Invalid opponent: {}", e);
+		process::exit(1);
+	});
+
+	let coverage = Coverage::new();
+
+	let reports = players.iter()
+		.take(args.top)
+		.enumerate()
+		.map(|(i, player)| {
+			let mut report = benchmark::run(i, player, &args.opponent, &mut opponent, args.games, rng);
+			report.coverage = Some(coverage.score(&player.neural_net, rng));
+			println!("{}", report);
+			report
+		})
+		.collect::<Vec<_>>();
+
+	if let Some(ref file_path) = args.json {
+		let written = json::encode(&reports)
+			.map_err(|e| e.to_string())
+			.and_then(|encoded| {
+				fs::File::create(file_path)
+					.and_then(|mut file| file.write_all(encoded.as_bytes()))
+					.map_err(|e| e.to_string())
+			});
+
+		if let Err(e) = written {
+			println!("Unable to write {}: {}", file_path, e);
+			process::exit(1);
+		}
+	}
+}
+
+/// Guides an MCTS opponent's playouts with the best saved player
+fn guide(opponent: Opponent) -> Result<Opponent, String> {
+	match persistance::load().and_then(|state| state.players.into_iter().next()) {
+		Some(best) => opponent.guided(best.neural_net),
+		None => Err("no saved player to guide the playouts".to_string()),
+	}
+}
+
+/// Plays a game between the human and another player
+fn play_human<P: Player>(human: &mut HumanPlayer, other: &mut P, human_first: bool, rng: &mut StdRng) {
+	if human_first {
+		play_game(human, other, rng);
+	} else {
+		play_game(other, human, rng);
+	}
+}
+
+/// Reports each generation on stdout
+struct Progress;
+
+impl genetics::Observer<AiPlayer> for Progress {
+	fn on_generation_end(&mut self,
+	                     stats: &genetics::GenerationStats,
+	                     players: &[AiPlayer],
+	                     _hall_of_fame: &[AiPlayer]) {
+		println!("Generation: {}, best: {}, mean: {:.2}, median: {}, worst: {}, std dev: {:.2}, species: {}",
+			stats.generation, stats.best, stats.mean, stats.median, stats.worst, stats.std_dev, stats.species);
+		println!("Generation: {}, {:?}", stats.generation, players[0]);
+	}
+}
+
+/// Lets a human play the best player after every `interval` generations
+struct HumanMatch {
+	interval: u32,
+	human: HumanPlayer,
+	human_first: bool,
+	rng: StdRng,
+}
+
+impl genetics::Observer<AiPlayer> for HumanMatch {
+	fn on_generation_end(&mut self,
+	                     stats: &genetics::GenerationStats,
+	                     players: &[AiPlayer],
+	                     _hall_of_fame: &[AiPlayer]) {
+		if (stats.generation + 1) % self.interval == 0 {
+			let mut best = AiPlayer::with_network(players[0].neural_net.clone());
+
+			play_human(&mut self.human, &mut best, self.human_first, &mut self.rng);
+		}
+	}
+}
+
 struct Args {
     num_players: usize,
     human: bool,
+    human_first: bool,
     debug: bool,
+    seed: Option<usize>,
+    selection: Box<genetics::Selection>,
+    survival_rate: f32,
+    elites: Option<usize>,
+    immigrant_rate: f32,
+    stats: Option<String>,
+    competition: String,
+    evaluate: Option<String>,
+    coverage: bool,
+    speciation: Option<f64>,
+    hall_of_fame: usize,
+    hall_of_fame_opponents: usize,
+    rating: Option<genetics::RatingSystem>,
+    generations: Option<u32>,
+    target_rating: Option<i32>,
+    target_score: Option<f64>,
+    opponent: Option<String>,
+    guided: bool,
+    stagnation: Option<u32>,
+    time_limit: Option<u64>,
+    stats_format: genetics::StatsFormat,
 	examples: Option<String>,
+	generate_examples: Option<String>,
+	learning_rate: f32,
+	batch_size: usize,
+	epochs: u32,
+	target_loss: f32,
+	crossover: neural::Crossover,
+	mutation: neural::MutationConfig,
+	benchmark: Option<BenchmarkArgs>,
+}
+
+struct BenchmarkArgs {
+	opponent: String,
+	games: u32,
+	top: usize,
+	file: Option<String>,
+	json: Option<String>,
 }
 
 fn get_args() -> Args {
@@ -88,20 +404,296 @@ fn get_args() -> Args {
 			.help("File of training examples to use.")
 			.takes_value(true)
 			.value_name("FILE"))
+		.arg(clap::Arg::with_name("generate-examples")
+			.long("generate-examples")
+			.help("Writes perfect play for every position to FILE as training examples.")
+			.takes_value(true)
+			.value_name("FILE"))
+		.arg(clap::Arg::with_name("rate")
+			.long("rate")
+			.help("Sets the learning rate used when training on examples.")
+			.takes_value(true)
+			.value_name("RATE")
+			.default_value("0.5"))
+		.arg(clap::Arg::with_name("batch")
+			.long("batch")
+			.help("Sets the number of examples in each training mini-batch.")
+			.takes_value(true)
+			.value_name("NUM")
+			.default_value("1"))
+		.arg(clap::Arg::with_name("epochs")
+			.long("epochs")
+			.help("Sets the maximum number of passes over the examples.")
+			.takes_value(true)
+			.value_name("NUM")
+			.default_value("1000"))
+		.arg(clap::Arg::with_name("loss")
+			.long("loss")
+			.help("Stops training once the mean loss falls to this value.")
+			.takes_value(true)
+			.value_name("LOSS")
+			.default_value("0.001"))
+		.arg(clap::Arg::with_name("selection")
+			.long("selection")
+			.help("Sets how parents are chosen: truncation, tournament, roulette, rank or sus.")
+			.takes_value(true)
+			.value_name("STRATEGY")
+			.default_value("truncation"))
+		.arg(clap::Arg::with_name("tournament-size")
+			.long("tournament-size")
+			.help("Sets the number of individuals in each selection tournament.")
+			.takes_value(true)
+			.value_name("NUM")
+			.default_value("3"))
+		.arg(clap::Arg::with_name("survival-rate")
+			.long("survival-rate")
+			.help("Sets the fraction of each generation allowed to breed.")
+			.takes_value(true)
+			.value_name("RATE")
+			.default_value("0.5"))
+		.arg(clap::Arg::with_name("elites")
+			.long("elites")
+			.help("Sets the number of the best players kept unchanged. Defaults to every survivor.")
+			.takes_value(true)
+			.value_name("NUM"))
+		.arg(clap::Arg::with_name("immigrant-rate")
+			.long("immigrant-rate")
+			.help("Sets the fraction of each generation replaced by new players.")
+			.takes_value(true)
+			.value_name("RATE")
+			.default_value("0"))
+		.arg(clap::Arg::with_name("stats")
+			.long("stats")
+			.help("File to write the statistics of each generation to.")
+			.takes_value(true)
+			.value_name("FILE"))
+		.arg(clap::Arg::with_name("stats-format")
+			.long("stats-format")
+			.help("Sets the statistics file format: csv or json.")
+			.takes_value(true)
+			.value_name("FORMAT")
+			.default_value("csv"))
+		.arg(clap::Arg::with_name("competition")
+			.long("competition")
+			.help("Sets who plays whom: round-robin, N-random, N-swiss or N-benchmark.")
+			.takes_value(true)
+			.value_name("SCHEME")
+			.default_value("round-robin"))
+		.arg(clap::Arg::with_name("evaluate")
+			.long("evaluate")
+			.help("Scores players against the labelled examples in FILE instead of having them play.")
+			.takes_value(true)
+			.value_name("FILE"))
+		.arg(clap::Arg::with_name("coverage")
+			.long("coverage")
+			.help("Scores players by how many of every reachable position they play perfectly instead of having them play.")
+			.conflicts_with("evaluate"))
+		.arg(clap::Arg::with_name("speciation")
+			.long("speciation")
+			.help("Groups players whose weights differ by less than this RMS distance into species.")
+			.takes_value(true)
+			.value_name("DISTANCE"))
+		.arg(clap::Arg::with_name("hall-of-fame")
+			.long("hall-of-fame")
+			.help("Sets how many past champions are kept in the hall of fame.")
+			.takes_value(true)
+			.value_name("NUM")
+			.default_value("0"))
+		.arg(clap::Arg::with_name("hall-of-fame-opponents")
+			.long("hall-of-fame-opponents")
+			.help("Sets how many hall of fame members each generation plays.")
+			.takes_value(true)
+			.value_name("NUM")
+			.default_value("5"))
+		.arg(clap::Arg::with_name("rating")
+			.long("rating")
+			.help("Rates players with elo or glicko2 instead of their win, tie and loss counts.")
+			.takes_value(true)
+			.value_name("SYSTEM"))
+		.arg(clap::Arg::with_name("generations")
+			.long("generations")
+			.help("Stops after this many generations.")
+			.takes_value(true)
+			.value_name("NUM"))
+		.arg(clap::Arg::with_name("target-rating")
+			.long("target-rating")
+			.help("Stops once the best player reaches this rating.")
+			.takes_value(true)
+			.value_name("RATING"))
+		.arg(clap::Arg::with_name("target-score")
+			.long("target-score")
+			.help("Stops once the best player scores this fraction of points against --opponent.")
+			.takes_value(true)
+			.value_name("SCORE"))
+		.arg(clap::Arg::with_name("opponent")
+			.long("opponent")
+			.help("Fixed player to measure --target-score against, heuristic by default. With --human, plays the human instead of evolving. One of random, heuristic, minimax[:DEPTH] or mcts[:BUDGET[:EXPLORATION]], where BUDGET is a number of playouts or a time such as 200ms.")
+			.takes_value(true)
+			.value_name("PLAYER"))
+		.arg(clap::Arg::with_name("guided")
+			.long("guided")
+			.help("Guides an mcts --opponent's playouts with the best saved player.")
+			.requires("opponent"))
+		.arg(clap::Arg::with_name("stagnation")
+			.long("stagnation")
+			.help("Stops once the best rating hasn't improved for this many generations.")
+			.takes_value(true)
+			.value_name("NUM"))
+		.arg(clap::Arg::with_name("time-limit")
+			.long("time-limit")
+			.help("Stops after this many seconds.")
+			.takes_value(true)
+			.value_name("SECONDS"))
+		.arg(clap::Arg::with_name("crossover")
+			.long("crossover")
+			.help("Sets how parents are combined: uniform, neuron, layer, blend or N-point.")
+			.takes_value(true)
+			.value_name("MODE")
+			.default_value("uniform"))
+		.arg(clap::Arg::with_name("mutation-rate")
+			.long("mutation-rate")
+			.help("Sets the chance of mutating each weight of a child.")
+			.takes_value(true)
+			.value_name("RATE")
+			.default_value("0.02"))
+		.arg(clap::Arg::with_name("mutation-sigma")
+			.long("mutation-sigma")
+			.help("Mutates by adding Gaussian noise with this standard deviation instead of replacing weights.")
+			.takes_value(true)
+			.value_name("SIGMA"))
+		.arg(clap::Arg::with_name("weight-limit")
+			.long("weight-limit")
+			.help("Clamps mutated weights to this magnitude.")
+			.takes_value(true)
+			.value_name("LIMIT"))
+		.arg(clap::Arg::with_name("mutate-biases")
+			.long("mutate-biases")
+			.help("Mutates biases as well as weights."))
     	.arg(clap::Arg::with_name("human")
     		.short("H")
     		.long("human")
     		.help("Would the human like to play?"))
+    	.arg(clap::Arg::with_name("human-first")
+    		.long("human-first")
+    		.help("Lets the human move first instead of second.")
+    		.requires("human"))
+    	.arg(clap::Arg::with_name("seed")
+    		.long("seed")
+    		.help("Seeds the random number generator so runs can be reproduced.")
+    		.takes_value(true)
+    		.value_name("NUM"))
     	.arg(clap::Arg::with_name("debug")
     	    .long("debug")
     	    .help("Turn on debug output"))
+		.subcommand(clap::SubCommand::with_name("benchmark")
+			.about("Plays saved players against a reference opponent and reports how they did.")
+			.arg(clap::Arg::with_name("opponent")
+				.long("opponent")
+				.help("The reference player to play against, given like the top level --opponent.")
+				.takes_value(true)
+				.value_name("PLAYER")
+				.default_value("minimax"))
+			.arg(clap::Arg::with_name("games")
+				.short("n")
+				.long("games")
+				.help("Sets the number of games to play as X and as O.")
+				.takes_value(true)
+				.value_name("NUM")
+				.default_value("100"))
+			.arg(clap::Arg::with_name("top")
+				.long("top")
+				.help("Sets how many of the saved players, best first, to benchmark.")
+				.takes_value(true)
+				.value_name("NUM")
+				.default_value("1"))
+			.arg(clap::Arg::with_name("file")
+				.long("file")
+				.help("Loads the players from FILE instead of state.json.")
+				.takes_value(true)
+				.value_name("FILE"))
+			.arg(clap::Arg::with_name("json")
+				.long("json")
+				.help("Also writes the results to FILE as JSON.")
+				.takes_value(true)
+				.value_name("FILE")))
     	.get_matches();
     	
     Args {
     	num_players: value_t_or_exit!(matches, "players", usize),
     	human: matches.is_present("human"),
+    	human_first: matches.is_present("human-first"),
     	debug: matches.is_present("debug"),
+    	selection: genetics::selection::parse(matches.value_of("selection").unwrap(),
+    	                                      value_t_or_exit!(matches, "tournament-size", usize))
+    		.unwrap_or_else(|e| clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit()),
+    	survival_rate: value_t_or_exit!(matches, "survival-rate", f32),
+    	elites: if matches.is_present("elites") {
+    		Some(value_t_or_exit!(matches, "elites", usize))
+    	} else {
+    		None
+    	},
+    	immigrant_rate: value_t_or_exit!(matches, "immigrant-rate", f32),
+    	stats: matches.value_of("stats").map(|s| s.to_string()),
+    	competition: matches.value_of("competition").unwrap().to_string(),
+    	evaluate: matches.value_of("evaluate").map(|s| s.to_string()),
+    	coverage: matches.is_present("coverage"),
+    	speciation: optional_value(&matches, "speciation"),
+    	rating: optional_value(&matches, "rating"),
+    	hall_of_fame: value_t_or_exit!(matches, "hall-of-fame", usize),
+    	hall_of_fame_opponents: value_t_or_exit!(matches, "hall-of-fame-opponents", usize),
+    	generations: optional_value(&matches, "generations"),
+    	target_rating: optional_value(&matches, "target-rating"),
+    	target_score: optional_value(&matches, "target-score"),
+    	opponent: matches.value_of("opponent").map(|s| s.to_string()),
+    	guided: matches.is_present("guided"),
+    	stagnation: optional_value(&matches, "stagnation").map(|generations| {
+    		if generations == 0 {
+    			invalid_value("--stagnation needs at least 1 generation");
+    		}
+    		generations
+    	}),
+    	time_limit: optional_value(&matches, "time-limit"),
+    	stats_format: value_t_or_exit!(matches, "stats-format", genetics::StatsFormat),
+    	seed: if matches.is_present("seed") {
+    		Some(value_t_or_exit!(matches, "seed", usize))
+    	} else {
+    		None
+    	},
 		examples: matches.value_of("examples").map(|s| s.to_string()),
+		generate_examples: matches.value_of("generate-examples").map(|s| s.to_string()),
+		learning_rate: value_t_or_exit!(matches, "rate", f32),
+		batch_size: match value_t_or_exit!(matches, "batch", usize) {
+			0 => invalid_value("--batch needs at least 1 example"),
+			size => size,
+		},
+		epochs: value_t_or_exit!(matches, "epochs", u32),
+		target_loss: value_t_or_exit!(matches, "loss", f32),
+		crossover: value_t_or_exit!(matches, "crossover", neural::Crossover),
+		mutation: neural::MutationConfig::new(value_t_or_exit!(matches, "mutation-rate", f32),
+		                                      optional_value(&matches, "mutation-sigma"),
+		                                      optional_value(&matches, "weight-limit"),
+		                                      matches.is_present("mutate-biases"))
+			.unwrap_or_else(|e| invalid_value(&e)),
+		benchmark: matches.subcommand_matches("benchmark").map(|matches| BenchmarkArgs {
+			opponent: matches.value_of("opponent").unwrap().to_string(),
+			games: value_t_or_exit!(matches, "games", u32),
+			top: value_t_or_exit!(matches, "top", usize),
+			file: matches.value_of("file").map(|s| s.to_string()),
+			json: matches.value_of("json").map(|s| s.to_string()),
+		}),
     }
 }
 
+/// Exits with an error about an argument's value
+fn invalid_value(message: &str) -> ! {
+	clap::Error::with_description(message, clap::ErrorKind::InvalidValue).exit()
+}
+
+/// Parses an optional argument, exiting with an error if it's invalid
+fn optional_value<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Option<T> {
+	if matches.is_present(name) {
+		Some(value_t_or_exit!(matches, name, T))
+	} else {
+		None
+	}
+}
@@ -0,0 +1,118 @@
+/// Slope of `LeakyRelu` for negative inputs
+const LEAKY_SLOPE: f32 = 0.01;
+
+/// The function applied to a layer's weighted sums to produce its outputs
+#[derive(RustcEncodable, RustcDecodable, Copy, Clone, Debug, PartialEq)]
+pub enum Activation {
+    Sigmoid,
+    Tanh,
+    Relu,
+    LeakyRelu,
+    Linear,
+    /// Normalises the whole layer into a probability distribution. Intended
+    /// for output layers.
+    Softmax,
+}
+
+impl Activation {
+
+    /// Applies the activation in place to a layer's weighted sums
+    pub fn apply(&self, values: &mut [f32]) {
+        match *self {
+            Activation::Softmax => {
+                // Shift by the max to keep exp() from overflowing
+                let max = values.iter().fold(::std::f32::NEG_INFINITY, |max, &x| max.max(x));
+                let mut sum = 0f32;
+
+                for value in values.iter_mut() {
+                    *value = (*value - max).exp();
+                    sum += *value;
+                }
+
+                for value in values.iter_mut() {
+                    *value /= sum;
+                }
+            }
+            _ => {
+                for value in values.iter_mut() {
+                    *value = self.apply_single(*value);
+                }
+            }
+        }
+    }
+
+    fn apply_single(&self, x: f32) -> f32 {
+        match *self {
+            Activation::Sigmoid => sigmoid(x),
+            Activation::Tanh => x.tanh(),
+            Activation::Relu => x.max(0f32),
+            Activation::LeakyRelu => if x > 0f32 { x } else { LEAKY_SLOPE * x },
+            Activation::Linear => x,
+            Activation::Softmax => unreachable!(),
+        }
+    }
+
+    /// Returns the derivative of the activation with respect to its input,
+    /// given the activation's output.
+    ///
+    /// Softmax depends on every value in the layer so only the diagonal of
+    /// its Jacobian is returned. As an output layer it is trained against
+    /// cross-entropy loss instead, which avoids needing the derivative.
+    pub fn derivative(&self, output: f32) -> f32 {
+        match *self {
+            Activation::Sigmoid | Activation::Softmax => output * (1f32 - output),
+            Activation::Tanh => 1f32 - output * output,
+            Activation::Relu => if output > 0f32 { 1f32 } else { 0f32 },
+            Activation::LeakyRelu => if output > 0f32 { 1f32 } else { LEAKY_SLOPE },
+            Activation::Linear => 1f32,
+        }
+    }
+}
+
+pub fn sigmoid(x: f32) -> f32 {
+    1f32 / (1f32 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigmoid() {
+        assert_eq!(0.62245935, sigmoid(0.5));
+        assert_eq!(0.37754068, sigmoid(-0.5));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut values = [-2.0, 0.5];
+
+        Activation::Relu.apply(&mut values);
+        assert_eq!([0.0, 0.5], values);
+
+        let mut values = [-2.0, 0.5];
+
+        Activation::LeakyRelu.apply(&mut values);
+        assert_eq!([-0.02, 0.5], values);
+    }
+
+    #[test]
+    fn test_softmax() {
+        let mut values = [1.0, 2.0, 3.0];
+
+        Activation::Softmax.apply(&mut values);
+
+        assert!((1.0 - values.iter().fold(0.0, |sum, x| sum + x)).abs() < 1e-6);
+        assert!(values[0] < values[1] && values[1] < values[2]);
+        assert!((0.66524096 - values[2]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_derivative() {
+        assert_eq!(0.25, Activation::Sigmoid.derivative(0.5));
+        assert_eq!(0.75, Activation::Tanh.derivative(0.5));
+        assert_eq!(0.0, Activation::Relu.derivative(0.0));
+        assert_eq!(LEAKY_SLOPE, Activation::LeakyRelu.derivative(-0.1));
+        assert_eq!(1.0, Activation::Linear.derivative(-3.0));
+    }
+}
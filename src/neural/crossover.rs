@@ -0,0 +1,260 @@
+use rand::{self, Rng};
+use std::str::FromStr;
+use super::Network;
+use super::network::Layer;
+
+/// How the weights and biases of two parent networks are combined into a
+/// child
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Crossover {
+    /// Each weight and bias is taken from either parent
+    Uniform,
+    /// Each neuron's weights and bias are taken together from one parent
+    Neuron,
+    /// Each layer is taken whole from one parent
+    Layer,
+    /// The flattened genome of every layer's weights followed by its biases
+    /// is cut at this many random points, alternating parents between them.
+    /// `Points(1)` is single point crossover.
+    Points(usize),
+    /// Every value is a weighted average of the parents, with one random
+    /// weighting for the whole child
+    Blend,
+}
+
+impl Default for Crossover {
+    fn default() -> Crossover {
+        Crossover::Uniform
+    }
+}
+
+impl FromStr for Crossover {
+    type Err = String;
+
+    /// Parses `uniform`, `neuron`, `layer`, `blend` or `N-point`
+    fn from_str(s: &str) -> Result<Crossover, String> {
+        match s {
+            "uniform" => Ok(Crossover::Uniform),
+            "neuron" => Ok(Crossover::Neuron),
+            "layer" => Ok(Crossover::Layer),
+            "blend" => Ok(Crossover::Blend),
+            _ if s.ends_with("-point") => {
+                match s.trim_end_matches("-point").parse() {
+                    Ok(0) | Err(_) => Err(format!("invalid number of points in '{}'", s)),
+                    Ok(n) => Ok(Crossover::Points(n)),
+                }
+            }
+            _ => Err(format!("unknown crossover '{}'", s)),
+        }
+    }
+}
+
+impl Crossover {
+
+    /// Combines two parents with the same dimensions into a child
+    pub fn combine<R: Rng>(&self, a: &Network, b: &Network, rng: &mut R) -> Network {
+        assert_eq!(a.num_inputs, b.num_inputs);
+        assert_eq!(a.layers.len(), b.layers.len());
+
+        let genome_len = a.layers.iter()
+            .fold(0, |len, layer| len + layer.weights.len() + layer.biases.len());
+
+        // Genes at or after each cut switch to the other parent
+        let mut cuts = match *self {
+            Crossover::Points(n) if genome_len > 1 => rand::sample(rng, 1..genome_len, n),
+            _ => Vec::new(),
+        };
+        cuts.sort();
+
+        let alpha = match *self {
+            Crossover::Blend => rng.gen::<f32>(),
+            _ => 0f32,
+        };
+
+        let mut gene = 0;
+        let mut next_cut = 0;
+        let mut segment_from_a = true;
+        let mut layers = Vec::with_capacity(a.layers.len());
+
+        for (layer_a, layer_b) in a.layers.iter().zip(&b.layers) {
+            assert_eq!(layer_a.activation, layer_b.activation);
+            assert_eq!(layer_a.num_inputs, layer_b.num_inputs);
+            assert_eq!(layer_a.weights.len(), layer_b.weights.len());
+            assert_eq!(layer_a.biases.len(), layer_b.biases.len());
+
+            let num_inputs = layer_a.num_inputs;
+            let layer_from_a = rng.gen::<bool>();
+            let neurons_from_a = match *self {
+                Crossover::Neuron => (0..layer_a.num_neurons()).map(|_| rng.gen()).collect(),
+                _ => Vec::new(),
+            };
+
+            let mut inherit = |rng: &mut R, neuron: usize, x: f32, y: f32| {
+                while next_cut < cuts.len() && cuts[next_cut] <= gene {
+                    segment_from_a = !segment_from_a;
+                    next_cut += 1;
+                }
+                gene += 1;
+
+                let from_a = match *self {
+                    Crossover::Uniform => rng.gen(),
+                    Crossover::Neuron => neurons_from_a[neuron],
+                    Crossover::Layer => layer_from_a,
+                    Crossover::Points(_) => segment_from_a,
+                    Crossover::Blend => return alpha * x + (1f32 - alpha) * y,
+                };
+
+                if from_a { x } else { y }
+            };
+
+            let weights = layer_a.weights.iter()
+                .zip(&layer_b.weights)
+                .enumerate()
+                .map(|(i, (&x, &y))| inherit(rng, i / num_inputs, x, y))
+                .collect();
+
+            let biases = layer_a.biases.iter()
+                .zip(&layer_b.biases)
+                .enumerate()
+                .map(|(j, (&x, &y))| inherit(rng, j, x, y))
+                .collect();
+
+            layers.push(Layer {
+                activation: layer_a.activation,
+                num_inputs: num_inputs,
+                biases: biases,
+                weights: weights,
+            });
+        }
+
+        Network {
+            num_inputs: a.num_inputs,
+            layers: layers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    const MODES: [Crossover; 6] = [
+        Crossover::Uniform,
+        Crossover::Neuron,
+        Crossover::Layer,
+        Crossover::Points(1),
+        Crossover::Points(3),
+        Crossover::Blend,
+    ];
+
+    /// A network with every weight and bias set to `value`
+    fn filled(value: f32) -> Network {
+        let mut net = Network::with_dimensions(&[3, 4, 2]);
+
+        for layer in &mut net.layers {
+            for x in layer.weights.iter_mut().chain(layer.biases.iter_mut()) {
+                *x = value;
+            }
+        }
+
+        net
+    }
+
+    /// Flattens the genome in the order used for point crossover
+    fn genome(net: &Network) -> Vec<f32> {
+        net.layers.iter()
+            .flat_map(|layer| layer.weights.iter().chain(&layer.biases).cloned())
+            .collect()
+    }
+
+    #[test]
+    fn test_shape() {
+        let (a, b) = (filled(1.0), filled(-1.0));
+        let mut rng = StdRng::from_seed(&[7]);
+
+        for mode in MODES.iter() {
+            let child = mode.combine(&a, &b, &mut rng);
+
+            assert_eq!(a.num_inputs, child.num_inputs);
+            assert_eq!(a.layers.len(), child.layers.len());
+
+            for (parent, layer) in a.layers.iter().zip(&child.layers) {
+                assert_eq!(parent.activation, layer.activation);
+                assert_eq!(parent.num_inputs, layer.num_inputs);
+                assert_eq!(parent.weights.len(), layer.weights.len());
+                assert_eq!(parent.biases.len(), layer.biases.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let a = Network::with_dimensions(&[3, 4, 2]);
+        let b = Network::with_dimensions(&[3, 4, 2]);
+
+        for mode in MODES.iter() {
+            let first = mode.combine(&a, &b, &mut StdRng::from_seed(&[42]));
+            let second = mode.combine(&a, &b, &mut StdRng::from_seed(&[42]));
+
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_neuron() {
+        let (a, b) = (filled(1.0), filled(-1.0));
+        let child = Crossover::Neuron.combine(&a, &b, &mut StdRng::from_seed(&[3]));
+
+        for layer in &child.layers {
+            for j in 0..layer.num_neurons() {
+                let bias = layer.biases[j];
+                assert!(layer.neuron_weights(j).iter().all(|&w| w == bias));
+            }
+        }
+    }
+
+    #[test]
+    fn test_layer() {
+        let (a, b) = (filled(1.0), filled(-1.0));
+        let child = Crossover::Layer.combine(&a, &b, &mut StdRng::from_seed(&[3]));
+
+        for layer in &child.layers {
+            let first = layer.weights[0];
+            assert!(layer.weights.iter().chain(&layer.biases).all(|&x| x == first));
+        }
+    }
+
+    #[test]
+    fn test_points() {
+        let (a, b) = (filled(1.0), filled(-1.0));
+
+        for n in 1..4 {
+            let child = Crossover::Points(n).combine(&a, &b, &mut StdRng::from_seed(&[n]));
+            let genes = genome(&child);
+
+            // Starts with the first parent and switches exactly n times
+            let switches = genes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+            assert_eq!(1.0, genes[0]);
+            assert_eq!(n, switches);
+        }
+    }
+
+    #[test]
+    fn test_blend() {
+        let (a, b) = (filled(1.0), filled(-1.0));
+        let child = Crossover::Blend.combine(&a, &b, &mut StdRng::from_seed(&[3]));
+        let genes = genome(&child);
+
+        assert!(genes.iter().all(|&x| x == genes[0] && x >= -1.0 && x <= 1.0));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(Crossover::Neuron), "neuron".parse());
+        assert_eq!(Ok(Crossover::Points(2)), "2-point".parse());
+        assert!("x-point".parse::<Crossover>().is_err());
+        assert!("0-point".parse::<Crossover>().is_err());
+        assert!("sideways".parse::<Crossover>().is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Training examples and the files they are loaded from.
+//!
+//! Examples can be stored in one of two formats. The text format has one
+//! example per line, the inputs and desired outputs separated by a `|`:
+//!
+//! ```text
+//! # Take the centre when the opponent opens in a corner
+//! 1 0 0 0 0 0 0 0 0 | 0 0 0 0 1 0 0 0 0
+//! ```
+//!
+//! Values are separated by whitespace, blank lines are ignored and anything
+//! after a `#` is a comment.
+//!
+//! The JSON format is an array of objects with `inputs` and `outputs` arrays:
+//!
+//! ```text
+//! [{"inputs": [1, 0, 0, 0, 0, 0, 0, 0, 0], "outputs": [0, 0, 0, 0, 1, 0, 0, 0, 0]}]
+//! ```
+//!
+//! A file whose first non-whitespace character is `[` is read as JSON.
+
+use super::Network;
+use rustc_serialize::json;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+
+/// A single supervised training example
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq)]
+pub struct Example {
+    pub inputs: Vec<f32>,
+    pub outputs: Vec<f32>,
+}
+
+/// An error encountered while loading examples
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Json(json::DecoderError),
+    /// A line of a text file could not be parsed
+    Parse { line: usize, message: String },
+    /// An example doesn't match the dimensions of the network. `line` is
+    /// only known for text files.
+    Dimensions {
+        example: usize,
+        line: Option<usize>,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref e) => write!(f, "{}", e),
+            LoadError::Json(ref e) => write!(f, "invalid JSON: {}", e),
+            LoadError::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+            LoadError::Dimensions { example, line, expected, found } => {
+                match line {
+                    Some(line) => write!(f, "line {}: ", line)?,
+                    None => write!(f, "example {}: ", example)?,
+                }
+                write!(f, "expected {} inputs and {} outputs, found {} inputs and {} outputs",
+                    expected.0, expected.1, found.0, found.1)
+            }
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn description(&self) -> &str {
+        match *self {
+            LoadError::Io(_) => "unable to read examples",
+            LoadError::Json(_) => "invalid JSON examples",
+            LoadError::Parse { .. } => "invalid example",
+            LoadError::Dimensions { .. } => "example doesn't match network",
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+impl From<json::DecoderError> for LoadError {
+    fn from(e: json::DecoderError) -> LoadError {
+        LoadError::Json(e)
+    }
+}
+
+/// Loads training examples for the given network from a file in either the
+/// text or JSON format
+pub fn load_examples(file_path: &str, network: &Network) -> Result<Vec<Example>, LoadError> {
+    let mut contents = String::new();
+
+    fs::File::open(file_path)?.read_to_string(&mut contents)?;
+
+    parse_examples(&contents, network.num_inputs, network.num_outputs())
+}
+
+/// Saves training examples to a file in the JSON format
+pub fn save_examples(file_path: &str, examples: &[Example]) -> Result<(), Box<error::Error>> {
+    let encoded = json::encode(&examples)?;
+
+    fs::File::create(file_path)?.write_all(encoded.as_bytes())?;
+    Ok(())
+}
+
+/// Parses examples from the contents of an example file, checking them
+/// against the expected number of inputs and outputs
+pub fn parse_examples(contents: &str, num_inputs: usize, num_outputs: usize)
+    -> Result<Vec<Example>, LoadError> {
+
+    if contents.trim_start().starts_with('[') {
+        let examples: Vec<Example> = json::decode(contents)?;
+
+        for (i, example) in examples.iter().enumerate() {
+            check_dimensions(example, num_inputs, num_outputs, i + 1, None)?;
+        }
+
+        Ok(examples)
+    } else {
+        let mut examples = Vec::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_num = i + 1;
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split('|');
+            let (inputs, outputs) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(inputs), Some(outputs), None) => (inputs, outputs),
+                _ => return Err(LoadError::Parse {
+                    line: line_num,
+                    message: "expected inputs and outputs separated by a single '|'".to_string(),
+                }),
+            };
+
+            let example = Example {
+                inputs: parse_values(inputs, line_num)?,
+                outputs: parse_values(outputs, line_num)?,
+            };
+
+            check_dimensions(&example, num_inputs, num_outputs, examples.len() + 1, Some(line_num))?;
+            examples.push(example);
+        }
+
+        Ok(examples)
+    }
+}
+
+fn parse_values(values: &str, line: usize) -> Result<Vec<f32>, LoadError> {
+    values.split_whitespace()
+        .map(|value| {
+            value.parse().map_err(|_| LoadError::Parse {
+                line: line,
+                message: format!("'{}' is not a number", value),
+            })
+        })
+        .collect()
+}
+
+fn check_dimensions(example: &Example,
+                    num_inputs: usize,
+                    num_outputs: usize,
+                    index: usize,
+                    line: Option<usize>)
+                    -> Result<(), LoadError> {
+
+    if example.inputs.len() != num_inputs || example.outputs.len() != num_outputs {
+        Err(LoadError::Dimensions {
+            example: index,
+            line: line,
+            expected: (num_inputs, num_outputs),
+            found: (example.inputs.len(), example.outputs.len()),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_text() {
+        let contents = "# comment\n\n1 0 | 0 1 1\n0.5 -1 | 1 0 0 # trailing\n";
+
+        let examples = parse_examples(contents, 2, 3).unwrap();
+
+        assert_eq!(vec![
+            Example { inputs: vec![1.0, 0.0], outputs: vec![0.0, 1.0, 1.0] },
+            Example { inputs: vec![0.5, -1.0], outputs: vec![1.0, 0.0, 0.0] },
+        ], examples);
+    }
+
+    #[test]
+    fn parse_text_bad_value() {
+        match parse_examples("1 0 | 0 1\n1 x | 0 1\n", 2, 2) {
+            Err(LoadError::Parse { line, .. }) => assert_eq!(2, line),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_text_wrong_dimensions() {
+        match parse_examples("# header\n1 0 | 0 1\n1 0 0 | 0 1\n", 2, 2) {
+            Err(LoadError::Dimensions { example, line, found, .. }) => {
+                assert_eq!(2, example);
+                assert_eq!(Some(3), line);
+                assert_eq!((3, 2), found);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_json() {
+        let contents = r#"[{"inputs": [1, 0], "outputs": [0, 1]}]"#;
+
+        let examples = parse_examples(contents, 2, 2).unwrap();
+
+        assert_eq!(vec![Example { inputs: vec![1.0, 0.0], outputs: vec![0.0, 1.0] }], examples);
+    }
+
+    #[test]
+    fn parse_json_wrong_dimensions() {
+        let contents = r#"[{"inputs": [1, 0], "outputs": [0, 1]}, {"inputs": [1], "outputs": [0, 1]}]"#;
+
+        match parse_examples(contents, 2, 2) {
+            Err(LoadError::Dimensions { example, line, .. }) => {
+                assert_eq!(2, example);
+                assert_eq!(None, line);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
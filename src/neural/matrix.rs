@@ -0,0 +1,77 @@
+//! Kernels over row-major weight matrices
+
+/// Computes `weights * inputs + biases` into `outputs`, where `weights` has
+/// a row of `inputs.len()` values for each bias
+pub fn mat_vec(weights: &[f32], biases: &[f32], inputs: &[f32], outputs: &mut Vec<f32>) {
+    let num_inputs = inputs.len();
+    debug_assert_eq!(weights.len(), biases.len() * num_inputs);
+
+    outputs.clear();
+
+    for (i, &bias) in biases.iter().enumerate() {
+        let row = &weights[i * num_inputs..(i + 1) * num_inputs];
+        let mut sum = bias;
+
+        for (weight, input) in row.iter().zip(inputs) {
+            sum += input * weight;
+        }
+
+        outputs.push(sum);
+    }
+}
+
+/// Computes `inputs * weightsᵀ + biases` into `outputs` for a batch of
+/// row-major inputs with `num_inputs` values per row. The outputs have a row
+/// of `biases.len()` values for each row of inputs.
+pub fn mat_mat(weights: &[f32], biases: &[f32], inputs: &[f32], num_inputs: usize, outputs: &mut Vec<f32>) {
+    let num_outputs = biases.len();
+    let batch_size = if num_inputs == 0 { 0 } else { inputs.len() / num_inputs };
+    debug_assert_eq!(weights.len(), num_outputs * num_inputs);
+    debug_assert_eq!(inputs.len(), batch_size * num_inputs);
+
+    outputs.clear();
+    outputs.resize(batch_size * num_outputs, 0f32);
+
+    // Each row of weights is applied to the whole batch while it is in cache
+    for (i, &bias) in biases.iter().enumerate() {
+        let row = &weights[i * num_inputs..(i + 1) * num_inputs];
+
+        for n in 0..batch_size {
+            let input = &inputs[n * num_inputs..(n + 1) * num_inputs];
+            let mut sum = bias;
+
+            for (weight, input) in row.iter().zip(input) {
+                sum += input * weight;
+            }
+
+            outputs[n * num_outputs + i] = sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mat_vec() {
+        let mut outputs = Vec::new();
+
+        mat_vec(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[0.5, -1.0], &[1.0, 0.0, -1.0], &mut outputs);
+
+        assert_eq!(vec![-1.5, -3.0], outputs);
+    }
+
+    #[test]
+    fn test_mat_mat() {
+        let mut outputs = Vec::new();
+
+        mat_mat(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+                &[0.5, -1.0],
+                &[1.0, 0.0, -1.0, 0.0, 1.0, 0.0],
+                3,
+                &mut outputs);
+
+        assert_eq!(vec![-1.5, -3.0, 2.5, 4.0], outputs);
+    }
+}
@@ -1,5 +1,14 @@
-pub use self::network::Network;
-pub use self::trainer::{Example, Trainer, load_examples};
+pub use self::activation::Activation;
+pub use self::crossover::Crossover;
+pub use self::example::{Example, load_examples, save_examples};
+pub use self::mutation::MutationConfig;
+pub use self::network::{Network, Scratch};
+pub use self::trainer::Trainer;
 
+mod activation;
+mod crossover;
+mod example;
+mod matrix;
+mod mutation;
 mod network;
 mod trainer;
\ No newline at end of file
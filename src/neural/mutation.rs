@@ -0,0 +1,150 @@
+use rand::Rng;
+use rand::distributions::{IndependentSample, Normal};
+
+/// Controls how weights and biases are mutated when networks reproduce
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MutationConfig {
+    /// Chance of mutating each inherited weight, between 0 and 1
+    pub rate: f32,
+    /// Standard deviation of the Gaussian noise added to a mutated value.
+    /// When `None` the value is replaced by a new one in [-1, 1].
+    pub sigma: Option<f32>,
+    /// Mutated values are clamped to [-limit, limit]
+    pub limit: Option<f32>,
+    /// Whether biases are mutated as well as weights
+    pub mutate_biases: bool,
+}
+
+impl Default for MutationConfig {
+    fn default() -> MutationConfig {
+        MutationConfig {
+            rate: 0.02,
+            sigma: None,
+            limit: None,
+            mutate_biases: false,
+        }
+    }
+}
+
+impl MutationConfig {
+
+    /// Constructs a `MutationConfig`, checking that the rate is between 0
+    /// and 1 and that the standard deviation and limit aren't negative
+    pub fn new(rate: f32, sigma: Option<f32>, limit: Option<f32>, mutate_biases: bool)
+               -> Result<MutationConfig, String> {
+        if !(rate >= 0f32 && rate <= 1f32) {
+            return Err(format!("mutation rate {} isn't between 0 and 1", rate));
+        }
+        if let Some(sigma) = sigma.filter(|sigma| !(*sigma >= 0f32)) {
+            return Err(format!("mutation standard deviation {} is negative", sigma));
+        }
+        if let Some(limit) = limit.filter(|limit| !(*limit >= 0f32)) {
+            return Err(format!("weight limit {} is negative", limit));
+        }
+
+        Ok(MutationConfig {
+            rate: rate,
+            sigma: sigma,
+            limit: limit,
+            mutate_biases: mutate_biases,
+        })
+    }
+
+    /// Returns the inherited weight, possibly mutated
+    pub fn mutate_weight<R: Rng>(&self, rng: &mut R, weight: f32) -> f32 {
+        self.mutate(rng, weight)
+    }
+
+    /// Returns the inherited bias, possibly mutated
+    pub fn mutate_bias<R: Rng>(&self, rng: &mut R, bias: f32) -> f32 {
+        if self.mutate_biases {
+            self.mutate(rng, bias)
+        } else {
+            bias
+        }
+    }
+
+    fn mutate<R: Rng>(&self, rng: &mut R, value: f32) -> f32 {
+        if rng.gen::<f32>() >= self.rate {
+            return value;
+        }
+
+        let mutated = match self.sigma {
+            Some(sigma) => value + Normal::new(0f64, sigma as f64).ind_sample(rng) as f32,
+            None => rng.gen::<f32>() * 2f32 - 1f32,
+        };
+
+        match self.limit {
+            Some(limit) => mutated.max(-limit).min(limit),
+            None => mutated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    #[test]
+    fn test_no_mutation() {
+        let mut rng = StdRng::from_seed(&[1, 2, 3]);
+        let config = MutationConfig {
+            rate: 0.0,
+            mutate_biases: true,
+            .. MutationConfig::default()
+        };
+
+        for _ in 0..100 {
+            assert_eq!(5.0, config.mutate_weight(&mut rng, 5.0));
+            assert_eq!(5.0, config.mutate_bias(&mut rng, 5.0));
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Ok(MutationConfig { rate: 1.0, sigma: Some(0.0), limit: Some(2.0), mutate_biases: true }),
+                   MutationConfig::new(1.0, Some(0.0), Some(2.0), true));
+
+        assert!(MutationConfig::new(1.5, None, None, false).is_err());
+        assert!(MutationConfig::new(-0.1, None, None, false).is_err());
+        assert!(MutationConfig::new(0.5, Some(-1.0), None, false).is_err());
+        assert!(MutationConfig::new(0.5, None, Some(-1.0), false).is_err());
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut rng = StdRng::from_seed(&[1, 2, 3]);
+        let config = MutationConfig {
+            rate: 1.0,
+            .. MutationConfig::default()
+        };
+
+        for _ in 0..100 {
+            let weight = config.mutate_weight(&mut rng, 5.0);
+            assert!(weight >= -1.0 && weight <= 1.0);
+
+            // Biases are left alone unless asked for
+            assert_eq!(5.0, config.mutate_bias(&mut rng, 5.0));
+        }
+    }
+
+    #[test]
+    fn test_gaussian_clamped() {
+        let mut rng = StdRng::from_seed(&[1, 2, 3]);
+        let config = MutationConfig {
+            rate: 1.0,
+            sigma: Some(0.5),
+            limit: Some(2.0),
+            mutate_biases: true,
+        };
+
+        let mut changed = false;
+        for _ in 0..100 {
+            let bias = config.mutate_bias(&mut rng, 1.9);
+            assert!(bias >= -2.0 && bias <= 2.0);
+            changed |= bias != 1.9;
+        }
+        assert!(changed);
+    }
+}
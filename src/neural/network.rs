@@ -1,51 +1,154 @@
-use rand::{self, thread_rng, Rng};
+use rand::{thread_rng, Rng};
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use std::mem;
+use super::{Activation, Crossover, MutationConfig};
+use super::matrix;
 
-#[derive(RustcEncodable, RustcDecodable, Debug)]
+#[derive(RustcEncodable, Clone, Debug, PartialEq)]
 pub struct Network {
     pub num_inputs: usize,
     pub layers: Vec<Layer>,
 }
 
-#[derive(RustcEncodable, RustcDecodable, Debug)]
+/// A fully connected layer of neurons.
+///
+/// The weights are stored as one contiguous row-major matrix with a row of
+/// `num_inputs` weights for each neuron.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Layer {
-    pub bias: f32,
-    pub neurons: Vec<Neuron>,
+    pub activation: Activation,
+    pub num_inputs: usize,
+    pub biases: Vec<f32>,
+    pub weights: Vec<f32>,
 }
 
-#[derive(RustcEncodable, RustcDecodable, Debug)]
-pub struct Neuron {
-    pub weights: Vec<f32>,
+/// A neuron as it is saved, which may be from before each neuron had its
+/// own bias
+#[derive(RustcDecodable)]
+struct SavedNeuron {
+    bias: Option<f32>,
+    weights: Vec<f32>,
+}
+
+// Layers are saved as a list of neurons, as they were before the weights
+// were flattened, so existing state files continue to load
+impl Encodable for Layer {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("Layer", 2, |s| {
+            s.emit_struct_field("activation", 0, |s| self.activation.encode(s))?;
+            s.emit_struct_field("neurons", 1, |s| {
+                s.emit_seq(self.num_neurons(), |s| {
+                    for i in 0..self.num_neurons() {
+                        s.emit_seq_elt(i, |s| {
+                            s.emit_struct("Neuron", 2, |s| {
+                                s.emit_struct_field("bias", 0, |s| self.biases[i].encode(s))?;
+                                s.emit_struct_field("weights", 1, |s| self.neuron_weights(i).encode(s))
+                            })
+                        })?;
+                    }
+                    Ok(())
+                })
+            })
+        })
+    }
+}
+
+impl Decodable for Layer {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Layer, D::Error> {
+        d.read_struct("Layer", 3, |d| {
+            // Older networks were always sigmoid and stored a single bias
+            // shared by the whole layer
+            let activation: Option<Activation> = d.read_struct_field("activation", 0, Decodable::decode)?;
+            let shared_bias: Option<f32> = d.read_struct_field("bias", 1, Decodable::decode)?;
+            let neurons: Vec<SavedNeuron> = d.read_struct_field("neurons", 2, Decodable::decode)?;
+            
+            let num_inputs = neurons.first().map_or(0, |neuron| neuron.weights.len());
+            let mut biases = Vec::with_capacity(neurons.len());
+            let mut weights = Vec::with_capacity(neurons.len() * num_inputs);
+            
+            for neuron in neurons {
+                if neuron.weights.len() != num_inputs {
+                    return Err(d.error("neurons in a layer must have the same number of weights"));
+                }
+                
+                match neuron.bias.or(shared_bias) {
+                    Some(bias) => biases.push(bias),
+                    None => return Err(d.error("neuron is missing a bias")),
+                }
+                
+                weights.extend(neuron.weights);
+            }
+            
+            Ok(Layer {
+                activation: activation.unwrap_or(Activation::Sigmoid),
+                num_inputs: num_inputs,
+                biases: biases,
+                weights: weights,
+            })
+        })
+    }
+}
+
+impl Decodable for Network {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Network, D::Error> {
+        d.read_struct("Network", 2, |d| {
+            let num_inputs: usize = d.read_struct_field("num_inputs", 0, Decodable::decode)?;
+            let layers: Vec<Layer> = d.read_struct_field("layers", 1, Decodable::decode)?;
+
+            // Each layer must take exactly the outputs of the one before it
+            let mut width = num_inputs;
+
+            for layer in &layers {
+                if layer.num_inputs != width {
+                    return Err(d.error(&format!("layer takes {} inputs but is given {}", layer.num_inputs, width)));
+                }
+                width = layer.num_neurons();
+            }
+
+            Ok(Network {
+                num_inputs: num_inputs,
+                layers: layers,
+            })
+        })
+    }
 }
 
 impl Network {
     
-    /// Constructs a new neural network with the specified dimensions
+    /// Constructs a new neural network with the specified dimensions using
+    /// sigmoid activation for every layer
     pub fn with_dimensions(dimensions: &[usize]) -> Network {
+        let activations = vec![Activation::Sigmoid; dimensions.len().saturating_sub(1)];
+        
+        Network::with_activations(dimensions, &activations, &mut thread_rng())
+    }
+    
+    /// Constructs a new neural network with the specified dimensions and an
+    /// activation for each layer after the inputs, randomly initialised from
+    /// the given generator
+    pub fn with_activations<R: Rng>(dimensions: &[usize],
+                                    activations: &[Activation],
+                                    rng: &mut R)
+                                    -> Network {
         assert!(dimensions.len() > 2);
+        assert_eq!(dimensions.len() - 1, activations.len());
         
-        let mut rng = thread_rng();
         let mut layers = Vec::with_capacity(dimensions.len() - 1);
         
         let mut prev_size = dimensions[0];
-        for &size in dimensions[1..].iter() {
-            let bias = rng.gen::<f32>() * 2f32 - 1f32;
-            let mut neurons = Vec::with_capacity(size);
-            
-            for _ in 0..size {
-                let mut weights = Vec::with_capacity(prev_size);
-                
-                for _ in 0..prev_size {
-                    weights.push(rng.gen::<f32>() * 2f32 - 1f32);
-                }
-                
-                neurons.push(Neuron {
-                    weights: weights,
-                });
-            }
+        for (&size, &activation) in dimensions[1..].iter().zip(activations) {
+            let biases = (0..size)
+                .map(|_| rng.gen::<f32>() * 2f32 - 1f32)
+                .collect();
+            let weights = (0..size * prev_size)
+                .map(|_| rng.gen::<f32>() * 2f32 - 1f32)
+                .collect();
             
             layers.push(Layer {
-                bias: bias,
-                neurons: neurons,
+                activation: activation,
+                num_inputs: prev_size,
+                biases: biases,
+                weights: weights,
             });
             
             prev_size = size;
@@ -57,94 +160,188 @@ impl Network {
         }
     }
     
-    pub fn reproduce(&self, partner: &Network) -> Network {
-        let mut rng = rand::thread_rng();
+    /// Returns the number of outputs produced by the final layer
+    pub fn num_outputs(&self) -> usize {
+        self.layers.last().map_or(self.num_inputs, |layer| layer.num_neurons())
+    }
+    
+    /// Returns the root mean square difference between the weights and biases
+    /// of two networks with the same dimensions
+    pub fn distance(&self, other: &Network) -> f32 {
+        assert_eq!(self.layers.len(), other.layers.len());
+
+        let mut sum = 0f32;
+        let mut count = 0;
+
+        for (a, b) in self.layers.iter().zip(&other.layers) {
+            assert_eq!(a.weights.len(), b.weights.len());
+            assert_eq!(a.biases.len(), b.biases.len());
+
+            let values = a.weights.iter().chain(&a.biases).zip(b.weights.iter().chain(&b.biases));
+
+            for (x, y) in values {
+                sum += (x - y) * (x - y);
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            0f32
+        } else {
+            (sum / count as f32).sqrt()
+        }
+    }
+
+    /// Produces a child network by crossover of two parents with the same
+    /// dimensions, followed by mutation
+    pub fn reproduce<R: Rng>(&self,
+                             partner: &Network,
+                             crossover: Crossover,
+                             mutation: &MutationConfig,
+                             rng: &mut R)
+                             -> Network {
+        let mut child = crossover.combine(self, partner, rng);
         
-        assert_eq!(self.layers.len(), partner.layers.len());
-        let layers = self.layers.iter()
-            .zip(&partner.layers)
-            .map(|(self_layer, partner_layer)| {
-                
-                assert_eq!(self_layer.neurons.len(), partner_layer.neurons.len());
-                let neurons = self_layer.neurons.iter()
-                    .zip(&partner_layer.neurons)
-                    .map(|(self_neuron, partner_neuron)| {
-                        
-                        assert_eq!(self_neuron.weights.len(), partner_neuron.weights.len());
-                        let weights = self_neuron.weights.iter()
-                            .zip(&partner_neuron.weights)
-                            .map(|(&a, &b)| {
-                                if rng.gen_weighted_bool(50) {
-                                    rng.gen::<f32>() * 2f32 - 1f32
-                                } 
-                                else if rng.gen() { a } else { b }
-                            })
-                            .collect();
-                            
-                        Neuron {
-                            weights: weights,
-                        }
-                    })
-                    .collect();
-                
-                Layer {
-                    bias: if rng.gen() { self_layer.bias } else { partner_layer.bias },
-                    neurons: neurons,
-                }
-            })
-            .collect();
+        child.mutate(mutation, rng);
+        child
+    }
+    
+    /// Mutates the weights and biases in place
+    pub fn mutate<R: Rng>(&mut self, mutation: &MutationConfig, rng: &mut R) {
+        for layer in &mut self.layers {
+            for weight in layer.weights.iter_mut() {
+                *weight = mutation.mutate_weight(rng, *weight);
+            }
+            
+            for bias in layer.biases.iter_mut() {
+                *bias = mutation.mutate_bias(rng, *bias);
+            }
+        }
+    }
+    
+    /// Runs the inputs through the network and returns the outputs of the
+    /// final layer. The buffers in `scratch` are reused between calls so no
+    /// allocation happens once they have grown to the widest layer.
+    pub fn evaluate<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        scratch.current.clear();
+        scratch.current.extend_from_slice(inputs);
         
-        Network {
-            num_inputs: self.num_inputs,
-            layers: layers,
+        for layer in &self.layers {
+            layer.feed(&scratch.current, &mut scratch.next);
+            mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+        
+        &scratch.current
+    }
+    
+    /// Forward propagation which returns the outputs of every layer,
+    /// starting with the inputs. Used for training and debugging.
+    pub fn run(&self, inputs: &[f32]) -> Vec<Vec<f32>> {
+        let mut outputs = Vec::with_capacity(self.layers.len() + 1);
+        outputs.push(Vec::from(inputs));
+        
+        for layer in &self.layers {
+            let mut layer_out = Vec::with_capacity(layer.num_neurons());
+            
+            layer.feed(outputs.last().unwrap(), &mut layer_out);
+            outputs.push(layer_out);
         }
+        
+        outputs
     }
     
-    // Forward Propagation
-    pub fn run(&mut self, inputs: &[f32]) -> Vec<Vec<f32>> {
-        let mut outputs = Vec::new();
-        let mut prev_layer_out = Vec::from(inputs);
+    /// Runs a batch of inputs through the network at once and returns the
+    /// outputs of the final layer. `inputs` holds `num_inputs` values for each
+    /// item in the batch, one after another, and the outputs are laid out the
+    /// same way with `num_outputs()` values per item.
+    pub fn evaluate_batch<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        scratch.current.clear();
+        scratch.current.extend_from_slice(inputs);
         
-        for layer in &mut self.layers {
+        for layer in &self.layers {
+            layer.feed_batch(&scratch.current, &mut scratch.next);
+            mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+        
+        &scratch.current
+    }
+    
+    /// Forward propagation of a batch which returns the outputs of every
+    /// layer, starting with the inputs, laid out as in `evaluate_batch`
+    pub fn run_batch(&self, inputs: &[f32]) -> Vec<Vec<f32>> {
+        let mut outputs = Vec::with_capacity(self.layers.len() + 1);
+        outputs.push(Vec::from(inputs));
+        
+        for layer in &self.layers {
             let mut layer_out = Vec::new();
             
-            for neuron in &mut layer.neurons {
-                let mut sum = layer.bias;
-                
-                for (weight, input) in &mut neuron.weights.iter().zip(&prev_layer_out) {
-                    sum += input * weight;
-                }
-                
-                layer_out.push(sigmoid(sum))
-            }
-            outputs.push(prev_layer_out);
-            prev_layer_out = layer_out;
+            layer.feed_batch(outputs.last().unwrap(), &mut layer_out);
+            outputs.push(layer_out);
         }
         
-        outputs.push(prev_layer_out);
-        
         outputs
     }
     
 }
 
-pub fn sigmoid(x: f32) -> f32 {
-    1f32 / (1f32 + (-x).exp())
+impl Layer {
+    
+    /// Returns the number of neurons, and so outputs, in this layer
+    pub fn num_neurons(&self) -> usize {
+        self.biases.len()
+    }
+    
+    /// Returns the incoming weights of a single neuron
+    pub fn neuron_weights(&self, neuron: usize) -> &[f32] {
+        &self.weights[neuron * self.num_inputs..(neuron + 1) * self.num_inputs]
+    }
+    
+    /// Replaces `outputs` with this layer's activations for the given inputs
+    fn feed(&self, inputs: &[f32], outputs: &mut Vec<f32>) {
+        assert_eq!(self.num_inputs, inputs.len());
+        
+        matrix::mat_vec(&self.weights, &self.biases, inputs, outputs);
+        self.activation.apply(outputs);
+    }
+    
+    /// Replaces `outputs` with this layer's activations for a batch of inputs
+    fn feed_batch(&self, inputs: &[f32], outputs: &mut Vec<f32>) {
+        assert!(self.num_inputs > 0 && inputs.len() % self.num_inputs == 0);
+        
+        matrix::mat_mat(&self.weights, &self.biases, inputs, self.num_inputs, outputs);
+        
+        if self.num_neurons() > 0 {
+            for row in outputs.chunks_mut(self.num_neurons()) {
+                self.activation.apply(row);
+            }
+        }
+    }
+}
+
+/// Reusable buffers for `Network::evaluate` and `Network::evaluate_batch`
+pub struct Scratch {
+    current: Vec<f32>,
+    next: Vec<f32>,
+}
+
+impl Scratch {
+    pub fn new() -> Scratch {
+        Scratch {
+            current: Vec::new(),
+            next: Vec::new(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use neural::{Example, Trainer};
+    use rustc_serialize::json;
     
-    #[test]
-    fn test_sigmoid() {
-        assert_eq!(0.62245935, sigmoid(0.5));
-        assert_eq!(0.37754068, sigmoid(-0.5));
-    }
-
     #[test]
     fn test_with_dimensions() {
-        let mut net = Network::with_dimensions(&[2, 3, 2]);
+        let net = Network::with_dimensions(&[2, 3, 2]);
 
         let output = net.run(&[1.0, 0.0]);
 
@@ -155,35 +352,39 @@ mod tests {
     }
 
     // https://mattmazur.com/2015/03/17/a-step-by-step-backpropagation-example/
-    #[test]
-    fn test_example() {
-        let mut net = Network {
+    fn example_network() -> Network {
+        Network {
             num_inputs: 2,
             layers: vec![
                 Layer {
-                    bias: 0.35,
-                    neurons: vec![
-                        Neuron {
-                            weights: vec![0.15, 0.2],
-                        },
-                        Neuron {
-                            weights: vec![0.25, 0.3],
-                        },
+                    activation: Activation::Sigmoid,
+                    num_inputs: 2,
+                    biases: vec![0.35, 0.35],
+                    weights: vec![
+                        0.15, 0.2,
+                        0.25, 0.3,
                     ],
                 },
                 Layer {
-                    bias: 0.60,
-                    neurons: vec![
-                        Neuron {
-                            weights: vec![0.4, 0.45],
-                        },
-                        Neuron {
-                            weights: vec![0.5, 0.55],
-                        },
+                    activation: Activation::Sigmoid,
+                    num_inputs: 2,
+                    biases: vec![0.60, 0.60],
+                    weights: vec![
+                        0.4, 0.45,
+                        0.5, 0.55,
                     ],
                 },
             ],
-        };
+        }
+    }
+
+    fn assert_close(expected: f32, actual: f32) {
+        assert!((expected - actual).abs() < 1e-6, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_example() {
+        let net = example_network();
  
         let output = net.run(&[0.05, 0.1]);
 
@@ -199,4 +400,170 @@ mod tests {
         assert_eq!(0.75136507, output[2][0]);
         assert_eq!(0.7729285, output[2][1]);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_example_backpropagation() {
+        let examples = [Example {
+            inputs: vec![0.05, 0.1],
+            outputs: vec![0.01, 0.99],
+        }];
+
+        let mut trainer = Trainer::new(example_network(), &examples);
+        
+        let loss = trainer.backpropagate(&examples[0]);
+        assert_close(0.298371109, loss);
+
+        let net = trainer.network();
+
+        // Hidden layer weights
+        assert_close(0.149780716, net.layers[0].weights[0]);
+        assert_close(0.19956143, net.layers[0].weights[1]);
+        assert_close(0.24975114, net.layers[0].weights[2]);
+        assert_close(0.29950229, net.layers[0].weights[3]);
+
+        // Output layer weights
+        assert_close(0.35891648, net.layers[1].weights[0]);
+        assert_close(0.408666186, net.layers[1].weights[1]);
+        assert_close(0.511301270, net.layers[1].weights[2]);
+        assert_close(0.561370121, net.layers[1].weights[3]);
+    }
+
+    #[test]
+    fn test_decode_shared_bias() {
+        let encoded = r#"{"num_inputs":2,"layers":[{"bias":0.35,"neurons":[{"weights":[0.15,0.2]},{"weights":[0.25,0.3]}]}]}"#;
+
+        let net: Network = json::decode(encoded).unwrap();
+
+        assert_eq!(1, net.layers.len());
+        assert_eq!(Activation::Sigmoid, net.layers[0].activation);
+        assert_eq!(2, net.layers[0].num_inputs);
+        assert_eq!(vec![0.35, 0.35], net.layers[0].biases);
+        assert_eq!(vec![0.15, 0.2, 0.25, 0.3], net.layers[0].weights);
+    }
+
+    #[test]
+    fn test_decode_mismatched_widths() {
+        // The second layer has 3 weights a neuron but the first has 2 neurons
+        let encoded = r#"{"num_inputs":2,"layers":[
+            {"bias":0.35,"neurons":[{"weights":[0.15,0.2]},{"weights":[0.25,0.3]}]},
+            {"bias":0.6,"neurons":[{"weights":[0.4,0.45,0.5]}]}]}"#;
+
+        assert!(json::decode::<Network>(encoded).is_err());
+
+        let encoded = r#"{"num_inputs":3,"layers":[{"bias":0.35,"neurons":[{"weights":[0.15,0.2]}]}]}"#;
+
+        assert!(json::decode::<Network>(encoded).is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let net = example_network();
+
+        let encoded = json::encode(&net).unwrap();
+        let decoded: Network = json::decode(&encoded).unwrap();
+
+        // Layers are still saved neuron by neuron
+        assert!(encoded.contains(r#""neurons":[{"bias":"#));
+        assert!(encoded.contains(r#"{"bias":0.6000000238418579,"weights":[0.5,"#));
+        assert_eq!(vec![0.60, 0.60], decoded.layers[1].biases);
+        assert_eq!(&[0.5, 0.55], decoded.layers[1].neuron_weights(1));
+    }
+
+    #[test]
+    fn test_distance() {
+        let network = example_network();
+        let mut other = example_network();
+
+        assert_eq!(0f32, network.distance(&other));
+
+        // Two of the twelve values differ by 0.6
+        other.layers[0].weights[0] += 0.6;
+        other.layers[1].biases[1] -= 0.6;
+        assert_close(0.6 * (2f32 / 12f32).sqrt(), network.distance(&other));
+    }
+
+    #[test]
+    fn test_with_activations() {
+        let net = Network::with_activations(&[2, 3, 2],
+                                            &[Activation::Relu, Activation::Softmax],
+                                            &mut thread_rng());
+
+        let output = net.run(&[1.0, 0.0]);
+
+        assert!(output[1].iter().all(|&x| x >= 0.0));
+        assert!((1.0 - output[2][0] - output[2][1]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let net = example_network();
+        let mut scratch = Scratch::new();
+
+        let expected = net.run(&[0.05, 0.1]).pop().unwrap();
+
+        assert_eq!(&expected[..], net.evaluate(&[0.05, 0.1], &mut scratch));
+        assert_eq!(&expected[..], net.evaluate(&[0.05, 0.1], &mut scratch));
+
+        // Scratch space can be shared with a network of different dimensions
+        let other = Network::with_dimensions(&[3, 5, 1]);
+        assert_eq!(1, other.evaluate(&[1.0, 0.0, 1.0], &mut scratch).len());
+    }
+
+    #[test]
+    fn test_evaluate_batch() {
+        let net = Network::with_activations(&[3, 4, 2],
+                                            &[Activation::Tanh, Activation::Softmax],
+                                            &mut thread_rng());
+        let mut scratch = Scratch::new();
+        let mut batch_scratch = Scratch::new();
+
+        let inputs = [
+            1.0, 0.0, -1.0,
+            0.5, 0.5, 0.0,
+            0.0, -1.0, 1.0,
+        ];
+
+        let outputs = net.evaluate_batch(&inputs, &mut batch_scratch);
+        assert_eq!(6, outputs.len());
+
+        for (input, output) in inputs.chunks(3).zip(outputs.chunks(2)) {
+            assert_eq!(net.evaluate(input, &mut scratch), output);
+        }
+
+        let trace = net.run_batch(&inputs);
+        assert_eq!(3, trace.len());
+        assert_eq!(12, trace[1].len());
+        assert_eq!(outputs, &trace[2][..]);
+    }
+
+    #[test]
+    fn test_batch_backpropagation() {
+        let example = Example {
+            inputs: vec![0.05, 0.1],
+            outputs: vec![0.01, 0.99],
+        };
+        let batch = [
+            Example { inputs: example.inputs.clone(), outputs: example.outputs.clone() },
+            Example { inputs: example.inputs.clone(), outputs: example.outputs.clone() },
+        ];
+
+        // A batch of identical examples has the same mean gradient as one
+        let mut single = Trainer::new(example_network(), &[]);
+        let mut batched = Trainer::new(example_network(), &[]);
+
+        let loss = single.backpropagate(&example);
+        assert_close(2.0 * loss, batched.backpropagate_batch(&batch));
+
+        let single = single.train();
+        let batched = batched.train();
+
+        for (a, b) in single.layers.iter().zip(&batched.layers) {
+            for (&a, &b) in a.weights.iter().zip(&b.weights) {
+                assert_close(a, b);
+            }
+            for (&a, &b) in a.biases.iter().zip(&b.biases) {
+                assert_close(a, b);
+            }
+        }
+    }
+}
\ No newline at end of file
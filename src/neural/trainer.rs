@@ -0,0 +1,190 @@
+use super::{Activation, Example, Network};
+use std::slice;
+
+/// Trains a `Network` against a set of examples using gradient descent
+/// backpropagation
+pub struct Trainer<'a> {
+    network: Network,
+    examples: &'a [Example],
+    learning_rate: f32,
+    batch_size: usize,
+    epochs: u32,
+    target_loss: f32,
+}
+
+impl<'a> Trainer<'a> {
+
+    /// Constructs a new `Trainer` for the given network and examples
+    pub fn new(network: Network, examples: &'a [Example]) -> Trainer<'a> {
+        Trainer {
+            network: network,
+            examples: examples,
+            learning_rate: 0.5,
+            batch_size: 1,
+            epochs: 1000,
+            target_loss: 0.001,
+        }
+    }
+
+    /// Sets how far the weights are moved along the gradient on each step
+    pub fn learning_rate(mut self, learning_rate: f32) -> Trainer<'a> {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    /// Sets the number of examples whose gradients are averaged for each
+    /// step
+    pub fn batch_size(mut self, batch_size: usize) -> Trainer<'a> {
+        assert!(batch_size > 0);
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets the maximum number of passes over the examples
+    pub fn epochs(mut self, epochs: u32) -> Trainer<'a> {
+        self.epochs = epochs;
+        self
+    }
+
+    /// Sets the mean loss per example at which training stops early
+    pub fn target_loss(mut self, target_loss: f32) -> Trainer<'a> {
+        self.target_loss = target_loss;
+        self
+    }
+
+    /// Returns the network as trained so far
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Trains until the epoch limit or the target loss is reached and
+    /// returns the trained network
+    pub fn train(mut self) -> Network {
+        if self.examples.is_empty() {
+            return self.network;
+        }
+
+        let examples = self.examples;
+
+        for epoch in 0..self.epochs {
+            let mut loss = 0f32;
+
+            for batch in examples.chunks(self.batch_size) {
+                loss += self.backpropagate_batch(batch);
+            }
+
+            loss /= examples.len() as f32;
+            debug!("Epoch: {}, loss: {}", epoch, loss);
+
+            if loss <= self.target_loss {
+                break;
+            }
+        }
+
+        self.network
+    }
+
+    /// Runs a single forward and backward pass for the example, updating the
+    /// network's weights. Returns the loss before the update.
+    pub fn backpropagate(&mut self, example: &Example) -> f32 {
+        self.backpropagate_batch(slice::from_ref(example))
+    }
+
+    /// Runs a forward and backward pass for a mini-batch of examples,
+    /// updating the network's weights by the mean gradient of the batch.
+    /// Returns the total loss of the batch before the update.
+    pub fn backpropagate_batch(&mut self, examples: &[Example]) -> f32 {
+        let batch_size = examples.len();
+        let num_layers = self.network.layers.len();
+
+        if batch_size == 0 {
+            return 0f32;
+        }
+
+        let mut inputs = Vec::with_capacity(batch_size * self.network.num_inputs);
+        let mut targets = Vec::with_capacity(batch_size * self.network.num_outputs());
+
+        for example in examples {
+            assert_eq!(self.network.num_inputs, example.inputs.len());
+            assert_eq!(self.network.num_outputs(), example.outputs.len());
+
+            inputs.extend_from_slice(&example.inputs);
+            targets.extend_from_slice(&example.outputs);
+        }
+
+        let outputs = self.network.run_batch(&inputs);
+        let final_out = &outputs[num_layers];
+
+        // Softmax outputs are trained against cross-entropy loss, whose
+        // gradient with respect to the sums is simply the error. Everything
+        // else uses squared error.
+        let output_activation = self.network.layers[num_layers - 1].activation;
+        let mut loss = 0f32;
+        let mut deltas = Vec::with_capacity(final_out.len());
+
+        for (&out, &target) in final_out.iter().zip(&targets) {
+            if output_activation == Activation::Softmax {
+                loss -= target * out.max(::std::f32::MIN_POSITIVE).ln();
+                deltas.push(out - target);
+            } else {
+                loss += 0.5 * (target - out) * (target - out);
+                deltas.push((out - target) * output_activation.derivative(out));
+            }
+        }
+
+        // Step along the mean gradient of the batch
+        let rate = self.learning_rate / batch_size as f32;
+
+        for l in (0..num_layers).rev() {
+            let layer_in = &outputs[l];
+            let input_activation = if l > 0 {
+                Some(self.network.layers[l - 1].activation)
+            } else {
+                None
+            };
+            let layer = &mut self.network.layers[l];
+            let num_inputs = layer.num_inputs;
+            let num_neurons = layer.num_neurons();
+
+            // Deltas for the previous layer must use the weights before
+            // they are updated
+            let prev_deltas = if let Some(input_activation) = input_activation {
+                layer_in.iter()
+                    .enumerate()
+                    .map(|(i, &input)| {
+                        let (n, k) = (i / num_inputs, i % num_inputs);
+                        let sum = deltas[n * num_neurons..(n + 1) * num_neurons].iter()
+                            .enumerate()
+                            .fold(0f32, |sum, (j, &delta)| sum + delta * layer.weights[j * num_inputs + k]);
+
+                        sum * input_activation.derivative(input)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for j in 0..num_neurons {
+                let row = &mut layer.weights[j * num_inputs..(j + 1) * num_inputs];
+                let mut bias_gradient = 0f32;
+
+                for n in 0..batch_size {
+                    let delta = deltas[n * num_neurons + j];
+                    let input = &layer_in[n * num_inputs..(n + 1) * num_inputs];
+
+                    for (weight, &input) in row.iter_mut().zip(input) {
+                        *weight -= rate * delta * input;
+                    }
+
+                    bias_gradient += delta;
+                }
+
+                layer.biases[j] -= rate * bias_gradient;
+            }
+
+            deltas = prev_deltas;
+        }
+
+        loss
+    }
+}
\ No newline at end of file
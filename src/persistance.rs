@@ -1,4 +1,5 @@
 use game::AiPlayer;
+use genetics::{GenerationStats, Observer};
 use rustc_serialize::json;
 use std::error;
 use std::fs;
@@ -6,9 +7,21 @@ use std::io::prelude::*;
 
 const STATE_FILE: &'static str = "state.json";
 
-pub fn load() -> Option<Vec<AiPlayer>> {
+/// Everything saved between runs
+#[derive(RustcEncodable, RustcDecodable)]
+pub struct State {
+    pub players: Vec<AiPlayer>,
+    pub hall_of_fame: Vec<AiPlayer>,
+}
+
+pub fn load() -> Option<State> {
+    load_from(STATE_FILE)
+}
+
+/// Loads a state saved to a file other than the default one
+pub fn load_from(file_path: &str) -> Option<State> {
     
-    if let Ok(mut file) = fs::File::open("state.json") {
+    if let Ok(mut file) = fs::File::open(file_path) {
         
         let mut encoded = String::new();
         
@@ -16,8 +29,11 @@ pub fn load() -> Option<Vec<AiPlayer>> {
             return None;
         }
         
-        if let Ok(players) = json::decode(&encoded) {
-            Some(players)
+        if let Ok(state) = json::decode(&encoded) {
+            Some(state)
+        } else if let Ok(players) = json::decode(&encoded) {
+            // Older states only saved the players
+            Some(State { players: players, hall_of_fame: Vec::new() })
         } else {
             None
         }
@@ -27,9 +43,13 @@ pub fn load() -> Option<Vec<AiPlayer>> {
     
 }
 
-pub fn save(players: &Vec<AiPlayer>) ->  Result<(), Box<error::Error>> {
+pub fn save(players: &[AiPlayer], hall_of_fame: &[AiPlayer]) ->  Result<(), Box<error::Error>> {
     
-    let encoded = json::encode(players)?.into_bytes();
+    let state = State {
+        players: players.to_vec(),
+        hall_of_fame: hall_of_fame.to_vec(),
+    };
+    let encoded = json::encode(&state)?.into_bytes();
     
     let mut file = fs::OpenOptions::new()
     	.write(true)
@@ -39,4 +59,26 @@ pub fn save(players: &Vec<AiPlayer>) ->  Result<(), Box<error::Error>> {
         .write_all(&encoded)?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Saves the players after every `interval` generations
+pub struct Checkpoint {
+    interval: u32,
+}
+
+impl Checkpoint {
+    pub fn new(interval: u32) -> Checkpoint {
+        assert!(interval > 0);
+        Checkpoint { interval: interval }
+    }
+}
+
+impl Observer<AiPlayer> for Checkpoint {
+    fn on_generation_end(&mut self, stats: &GenerationStats, players: &[AiPlayer], hall_of_fame: &[AiPlayer]) {
+        if (stats.generation + 1) % self.interval == 0 {
+            if let Err(e) = save(players, hall_of_fame) {
+                warn!("Unable to save {}: {}", STATE_FILE, e);
+            }
+        }
+    }
+}